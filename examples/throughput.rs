@@ -0,0 +1,32 @@
+extern crate suppositions;
+use suppositions::generators::*;
+
+// Run this with:
+// ```
+// cargo run --release --example throughput
+// ```
+// to see how many bytes and spans each generator draws per value, and how
+// quickly. Handy for spotting generators that could draw less data.
+
+const SAMPLES: usize = 10_000;
+
+fn report<G: Generator>(name: &str, gen: G) {
+    println!("{:>24}: {}", name, benchmark(&gen, SAMPLES));
+}
+
+fn main() {
+    report("booleans()", booleans());
+    report("u8s()", u8s());
+    report("u64s()", u64s());
+    report("f64s()", f64s());
+    report("optional(u8s())", optional(u8s()));
+    report(
+        "one_of(3 alternatives)",
+        one_of(consts(0u8)).or(consts(1)).or(u8s()),
+    );
+    report("choice(0..10)", choice((0..10).collect::<Vec<_>>()));
+    report("vecs(booleans())", vecs(booleans()));
+    report("vecs(u8s())", vecs(u8s()));
+    report("vecs(vecs(u8s()))", vecs(vecs(u8s())));
+    report("(u8s(), u32s())", (u8s(), u32s()));
+}
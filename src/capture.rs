@@ -0,0 +1,189 @@
+//! Captures output written whilst a test case runs, so that we only show
+//! the output for the minimal failing example, rather than for every
+//! attempt along the way.
+//!
+//! Log records are captured by installing a [`CaptureLogger`](struct.CaptureLogger.html)
+//! in place of your usual logger, eg:
+//!
+//! ```rust
+//! extern crate env_logger;
+//! extern crate log;
+//! extern crate suppositions;
+//! use suppositions::capture::CaptureLogger;
+//!
+//! fn init_logging() {
+//!     let inner = env_logger::Builder::from_default_env().build();
+//!     let max_level = inner.filter();
+//!     // Only one logger can be installed per process.
+//!     CaptureLogger::new(Box::new(inner))
+//!         .install(max_level)
+//!         .unwrap_or_default();
+//! }
+//! # fn main() { init_logging() }
+//! ```
+//!
+//! The standard `print!` macros write straight to the process's standard
+//! output, so cannot be captured on stable Rust. Instead, you can use
+//! [`print_captured!`](../macro.print_captured.html) and
+//! [`println_captured!`](../macro.println_captured.html), which
+//! behave like `print!` and `println!` outside of a test case.
+//!
+//! Panic messages raised by a test case are captured, too.
+
+use std::cell::RefCell;
+use std::fmt;
+
+use log::{self, LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+thread_local! {
+    static BUFFER: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// A logger that captures log records produced whilst running a test case,
+/// and passes any others on to an inner logger.
+pub struct CaptureLogger {
+    inner: Box<dyn Log>,
+}
+
+impl CaptureLogger {
+    /// Creates a logger that passes uncaptured records on to `inner`.
+    pub fn new(inner: Box<dyn Log>) -> Self {
+        CaptureLogger { inner }
+    }
+
+    /// Installs this as the global logger, and sets the maximum log level.
+    /// Fails if a logger has already been installed.
+    pub fn install(self, max_level: LevelFilter) -> Result<(), SetLoggerError> {
+        log::set_logger(Box::leak(Box::new(self)))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for CaptureLogger {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("CaptureLogger").finish()
+    }
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        is_capturing() || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !is_capturing() {
+            self.inner.log(record);
+            return;
+        }
+        // Formatting runs user code, so do it before borrowing the buffer.
+        let line = format!(
+            "{} {}: {}\n",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if !try_capture(&line) {
+            self.inner.log(record)
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+/// Writes `args` to the current test case's captured output, or to
+/// standard output if we aren't running a test case. Usually used via
+/// [`print_captured!`](../macro.print_captured.html).
+pub fn print(args: fmt::Arguments) {
+    if !is_capturing() {
+        print!("{}", args);
+        return;
+    }
+    let text = fmt::format(args);
+    if !try_capture(&text) {
+        print!("{}", text)
+    }
+}
+
+/// Like `print!`, but the output is captured whilst running a test case.
+/// See the [capture module](capture/index.html).
+#[macro_export]
+macro_rules! print_captured {
+    ($($arg:tt)*) => ($crate::capture::print(format_args!($($arg)*)));
+}
+
+/// Like `println!`, but the output is captured whilst running a test case.
+/// See the [capture module](capture/index.html).
+#[macro_export]
+macro_rules! println_captured {
+    () => ($crate::capture::print(format_args!("\n")));
+    ($($arg:tt)*) => ($crate::capture::print(format_args!("{}\n", format_args!($($arg)*))));
+}
+
+/// Runs `f`, and returns its result along with anything captured whilst
+/// it ran.
+pub(crate) fn capturing<T, F: FnOnce() -> T>(f: F) -> (T, String) {
+    let prev = BUFFER.with(|b| b.borrow_mut().replace(String::new()));
+    let res = f();
+    let captured = BUFFER.with(|b| ::std::mem::replace(&mut *b.borrow_mut(), prev));
+    (res, captured.unwrap_or_default())
+}
+
+/// Appends `text` to the capture buffer, and returns true, if we are
+/// capturing; otherwise returns false. Callers format `text` before we
+/// borrow the buffer, as formatting runs user code that may panic, and the
+/// panic hook captures the panic message in turn.
+pub(crate) fn try_capture(text: &str) -> bool {
+    BUFFER
+        .try_with(|b| match b.try_borrow_mut() {
+            Ok(mut buf) => match *buf {
+                Some(ref mut buf) => {
+                    buf.push_str(text);
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        })
+        .unwrap_or(false)
+}
+
+fn is_capturing() -> bool {
+    BUFFER.try_with(|b| b.borrow().is_some()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_capture_printed_output() {
+        let ((), out) = capturing(|| {
+            print_captured!("a{}", 1);
+            println_captured!(" b");
+        });
+        assert_eq!(out, "a1 b\n");
+    }
+
+    #[test]
+    fn should_not_capture_outside_of_capturing() {
+        assert!(!try_capture(""));
+        let ((), out) = capturing(|| ());
+        assert_eq!(out, "");
+        assert!(!try_capture(""));
+    }
+
+    #[test]
+    fn should_restore_outer_buffer_when_nested() {
+        let (inner, outer) = capturing(|| {
+            print_captured!("outer;");
+            let ((), inner) = capturing(|| print_captured!("inner"));
+            print_captured!("again");
+            inner
+        });
+        assert_eq!(inner, "inner");
+        assert_eq!(outer, "outer;again");
+    }
+}
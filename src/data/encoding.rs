@@ -0,0 +1,426 @@
+//! Stable text and binary encodings of an `InfoPool`. See the
+//! [module documentation](index.html#encoding) for the format.
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::Write;
+
+use super::source::*;
+
+const TEXT_HEADER: &str = "suppositions-pool";
+const MAGIC: &[u8] = b"SPPL";
+/// The current version of both encodings.
+pub const ENCODING_VERSION: u64 = 1;
+
+/// Describes why we could not decode a pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The encoding is from a version of the format we do not understand.
+    UnsupportedVersion(u64),
+    /// The encoding is not well formed; describes what we were decoding.
+    Malformed(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::UnsupportedVersion(v) => write!(fmt, "Unsupported pool version: {}", v),
+            DecodeError::Malformed(ref what) => write!(fmt, "Malformed pool: {}", what),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+fn malformed<T, S: Into<String>>(what: S) -> Result<T, DecodeError> {
+    Err(DecodeError::Malformed(what.into()))
+}
+
+impl InfoPool {
+    /// Encodes this pool as text. See the [encoding](index.html#encoding)
+    /// section of the module documentation.
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{} {}\ndata ", TEXT_HEADER, ENCODING_VERSION);
+        for b in self.data.iter() {
+            let _ = write!(out, "{:02x}", b);
+        }
+        out.push('\n');
+        for span in self.spans.iter() {
+            let kind = if span.dependent { "dependent" } else { "span" };
+            let _ = write!(out, "{} {} {} {}", kind, span.start, span.end, span.level);
+            if let Some(ref label) = span.label {
+                out.push(' ');
+                push_escaped_label(&mut out, label);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Decodes a pool encoded by [`to_text`](#method.to_text).
+    pub fn from_text(text: &str) -> Result<Self, DecodeError> {
+        let mut lines = text.lines();
+        let version = match lines.next().map(|l| l.split(' ').collect::<Vec<_>>()) {
+            Some(ref words) if words.len() == 2 && words[0] == TEXT_HEADER => {
+                parse_number(words[1])?
+            }
+            _ => return malformed("header"),
+        };
+        check_version(version)?;
+
+        let data = match lines.next().map(|l| l.split(' ').collect::<Vec<_>>()) {
+            Some(ref words) if words.len() == 2 && words[0] == "data" => parse_hex(words[1])?,
+            _ => return malformed("data"),
+        };
+
+        let mut spans = Vec::new();
+        for line in lines {
+            // Labels may contain spaces, so they take up the rest of the line.
+            let (fields, label) = match *line.splitn(5, ' ').collect::<Vec<_>>() {
+                [ref fields @ .., label] if fields.len() == 4 => {
+                    (fields.to_vec(), Some(Label::Owned(unescape_label(label)?)))
+                }
+                ref fields => (fields.to_vec(), None),
+            };
+            match fields[..] {
+                [kind, start, end, level] if kind == "span" || kind == "dependent" => {
+                    spans.push(Span {
+                        start: parse_number(start)? as usize,
+                        end: parse_number(end)? as usize,
+                        level: parse_number(level)? as usize,
+                        label,
+                        dependent: kind == "dependent",
+                    })
+                }
+                _ => return malformed(format!("span: {:?}", line)),
+            }
+        }
+        Self::checked(data, spans)
+    }
+
+    /// Encodes this pool in a compact binary form. See the
+    /// [encoding](index.html#encoding) section of the module documentation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        push_varint(&mut out, ENCODING_VERSION);
+        push_varint(&mut out, self.data.len() as u64);
+        out.extend_from_slice(&self.data);
+        push_varint(&mut out, self.spans.len() as u64);
+        for span in self.spans.iter() {
+            push_varint(&mut out, span.start as u64);
+            push_varint(&mut out, span.end as u64);
+            push_varint(&mut out, span.level as u64);
+            push_varint(&mut out, span.dependent as u64);
+            match span.label {
+                Some(ref label) => {
+                    push_varint(&mut out, label.len() as u64 + 1);
+                    out.extend_from_slice(label.as_bytes());
+                }
+                None => push_varint(&mut out, 0),
+            }
+        }
+        out
+    }
+
+    /// Decodes a pool encoded by [`to_bytes`](#method.to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if !bytes.starts_with(MAGIC) {
+            return malformed("magic");
+        }
+        let mut rest = &bytes[MAGIC.len()..];
+        let version = take_varint(&mut rest)?;
+        check_version(version)?;
+        let len = take_varint(&mut rest)? as usize;
+        if rest.len() < len {
+            return malformed("data");
+        }
+        let data = rest[..len].to_vec();
+        rest = &rest[len..];
+        let nspans = take_varint(&mut rest)?;
+        let mut spans = Vec::new();
+        for _ in 0..nspans {
+            let start = take_varint(&mut rest)? as usize;
+            let end = take_varint(&mut rest)? as usize;
+            let level = take_varint(&mut rest)? as usize;
+            let dependent = match take_varint(&mut rest)? {
+                0 => false,
+                1 => true,
+                _ => return malformed("dependent flag"),
+            };
+            let label = take_label(&mut rest)?;
+            spans.push(Span {
+                start,
+                end,
+                level,
+                label,
+                dependent,
+            });
+        }
+        if !rest.is_empty() {
+            return malformed("trailing bytes");
+        }
+        Self::checked(data, spans)
+    }
+
+    fn checked(data: Vec<u8>, spans: Vec<Span>) -> Result<Self, DecodeError> {
+        if let Some(span) = spans.iter().find(|s| s.start > s.end || s.end > data.len()) {
+            return malformed(format!("span out of bounds: {:?}", span));
+        }
+        Ok(InfoPool { data, spans })
+    }
+}
+
+fn check_version(version: u64) -> Result<(), DecodeError> {
+    if version == ENCODING_VERSION {
+        Ok(())
+    } else {
+        Err(DecodeError::UnsupportedVersion(version))
+    }
+}
+
+/// Labels in the text encoding take up the rest of their line, so we
+/// escape any line breaks (and backslashes) within them.
+fn push_escaped_label(out: &mut String, label: &str) {
+    for c in label.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn unescape_label(escaped: &str) -> Result<String, DecodeError> {
+    let mut label = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            label.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => label.push('\\'),
+            Some('n') => label.push('\n'),
+            Some('r') => label.push('\r'),
+            _ => return malformed(format!("label: {:?}", escaped)),
+        }
+    }
+    Ok(label)
+}
+
+fn parse_number(word: &str) -> Result<u64, DecodeError> {
+    word.parse()
+        .map_err(|_| DecodeError::Malformed(format!("number: {:?}", word)))
+}
+
+fn parse_hex(hex: &str) -> Result<Vec<u8>, DecodeError> {
+    if hex.len() & 1 != 0 || !hex.is_ascii() {
+        return malformed("hex data");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| DecodeError::Malformed("hex data".to_string()))
+        })
+        .collect()
+}
+
+fn push_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn take_varint(bytes: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut n = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&b, rest) = match bytes.split_first() {
+            Some(pair) => pair,
+            None => return malformed("truncated varint"),
+        };
+        *bytes = rest;
+        n |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    malformed("overlong varint")
+}
+
+fn take_label(bytes: &mut &[u8]) -> Result<Option<Label>, DecodeError> {
+    let len = match take_varint(bytes)? {
+        0 => return Ok(None),
+        n => (n - 1) as usize,
+    };
+    if bytes.len() < len {
+        return malformed("label");
+    }
+    let (label, rest) = bytes.split_at(len);
+    *bytes = rest;
+    match String::from_utf8(label.to_vec()) {
+        Ok(label) => Ok(Some(Label::Owned(label))),
+        Err(_) => malformed("label"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    struct Nested(usize);
+
+    impl InfoSink for Nested {
+        type Out = ();
+        fn sink<I: InfoSource>(&mut self, src: &mut I) {
+            let n = src.draw_u8() as usize % 4;
+            if self.0 > 0 {
+                for i in 0..n {
+                    if i % 2 == 0 {
+                        src.draw(Nested(self.0 - 1));
+                    } else {
+                        src.draw_dependent(Nested(self.0 - 1));
+                    }
+                }
+            }
+        }
+    }
+
+    fn recorded_pools() -> Vec<InfoPool> {
+        (0..100)
+            .map(|seed| {
+                let mut rec = InfoRecorder::new(RngSource::of(StdRng::seed_from_u64(seed)));
+                rec.draw(Nested(3));
+                rec.into_pool()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_encode_text_as_documented() {
+        let pool = InfoPool {
+            data: vec![0x80, 0x0b, 0x0c],
+            spans: vec![
+                Span {
+                    start: 0,
+                    end: 1,
+                    level: 1,
+                    label: Some(Label::Borrowed("bool")),
+                    dependent: false,
+                },
+                Span {
+                    start: 1,
+                    end: 3,
+                    level: 1,
+                    label: Some(Label::Borrowed("u16")),
+                    dependent: true,
+                },
+                Span {
+                    start: 0,
+                    end: 3,
+                    level: 0,
+                    label: Some(Label::Borrowed("Option<u16>")),
+                    dependent: false,
+                },
+            ],
+        };
+        assert_eq!(
+            pool.to_text(),
+            "suppositions-pool 1\ndata 800b0c\nspan 0 1 1 bool\ndependent 1 3 1 u16\n\
+             span 0 3 0 Option<u16>\n"
+        );
+    }
+
+    #[test]
+    fn should_decode_unlabelled_spans() {
+        let pool = InfoPool::from_text("suppositions-pool 1\ndata 0a0b0c\nspan 1 3 1\n").unwrap();
+        assert_eq!(pool.span_count(), 1);
+        assert_eq!(pool.spans[0].label, None);
+        assert_eq!(
+            InfoPool::from_bytes(b"SPPL\x01\x01\x0a\x01\x00\x01\x00\x00\x00"),
+            Ok(InfoPool {
+                data: vec![0x0a],
+                spans: vec![Span {
+                    start: 0,
+                    end: 1,
+                    level: 0,
+                    label: None,
+                    dependent: false,
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn should_round_trip_via_text() {
+        for pool in recorded_pools() {
+            assert_eq!(InfoPool::from_text(&pool.to_text()), Ok(pool));
+        }
+    }
+
+    #[test]
+    fn should_round_trip_via_bytes() {
+        for pool in recorded_pools() {
+            assert_eq!(InfoPool::from_bytes(&pool.to_bytes()), Ok(pool));
+        }
+    }
+
+    #[test]
+    fn should_round_trip_labels_with_line_breaks() {
+        let pool = InfoPool {
+            data: vec![0x0a],
+            spans: vec![Span {
+                start: 0,
+                end: 1,
+                level: 0,
+                label: Some(Label::Borrowed("two\nlines\r\\n")),
+                dependent: false,
+            }],
+        };
+        assert_eq!(
+            pool.to_text(),
+            "suppositions-pool 1\ndata 0a\nspan 0 1 0 two\\nlines\\r\\\\n\n"
+        );
+        assert_eq!(InfoPool::from_text(&pool.to_text()), Ok(pool.clone()));
+        assert_eq!(InfoPool::from_bytes(&pool.to_bytes()), Ok(pool));
+    }
+
+    #[test]
+    fn should_round_trip_empty_pool() {
+        let pool = InfoPool::new();
+        assert_eq!(InfoPool::from_text(&pool.to_text()), Ok(pool.clone()));
+        assert_eq!(InfoPool::from_bytes(&pool.to_bytes()), Ok(pool));
+    }
+
+    #[test]
+    fn should_reject_unknown_versions() {
+        assert_eq!(
+            InfoPool::from_text("suppositions-pool 99\ndata \n"),
+            Err(DecodeError::UnsupportedVersion(99))
+        );
+        assert_eq!(
+            InfoPool::from_bytes(b"SPPL\x63\x00\x00"),
+            Err(DecodeError::UnsupportedVersion(99))
+        );
+    }
+
+    #[test]
+    fn should_reject_spans_outside_of_data() {
+        let res = InfoPool::from_text("suppositions-pool 1\ndata 00\nspan 0 2 0\n");
+        assert!(res.is_err(), "{:?}", res);
+    }
+
+    #[test]
+    fn should_reject_truncated_bytes() {
+        let bytes = recorded_pools()[0].to_bytes();
+        for len in 0..bytes.len() {
+            let res = InfoPool::from_bytes(&bytes[..len]);
+            assert!(res.is_err(), "{}: {:?}", len, res);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use super::source::*;
+
+/// Describes which bytes of a pool were drawn by which generator. See
+/// [`InfoPool::span_tree`](struct.InfoPool.html#method.span_tree).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Extent {
+    /// A run of bytes drawn directly, rather than by a nested draw.
+    Leaf(usize),
+    /// The extents drawn by a single call to
+    /// [`InfoSource::draw`](trait.InfoSource.html#method.draw), in order,
+    /// along with the span's label. The root extent has no label.
+    Branch(Option<Label>, Vec<Extent>),
+}
+
+impl Extent {
+    /// The number of bytes covered by this extent.
+    pub fn len(&self) -> usize {
+        match *self {
+            Extent::Leaf(n) => n,
+            Extent::Branch(_, ref children) => children.iter().map(Extent::len).sum(),
+        }
+    }
+
+    /// Whether this extent covers no bytes at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl InfoPool {
+    /// Returns the structure of the spans recorded whilst generating from
+    /// this pool. The root is a branch covering the whole pool; each nested
+    /// draw becomes a branch; and bytes drawn directly become leaves. So the
+    /// extents cover each byte exactly once, in order.
+    pub fn span_tree(&self) -> Extent {
+        with_gaps(None, 0, self.data.len(), self.subtrees(None))
+    }
+
+    /// The extents of the children of the span at index `parent`, or of the
+    /// top level spans if `None`, along with the spans themselves.
+    fn subtrees(&self, parent: Option<usize>) -> Vec<(&Span, Extent)> {
+        self.children(parent)
+            .into_iter()
+            .map(|i| {
+                let span = &self.spans[i];
+                let children = self.subtrees(Some(i));
+                let extent = with_gaps(span.label.clone(), span.start, span.end, children);
+                (span, extent)
+            })
+            .collect()
+    }
+}
+
+/// Builds a branch covering `start..end`, filling the space between
+/// `children` with leaves.
+fn with_gaps(
+    label: Option<Label>,
+    start: usize,
+    end: usize,
+    children: Vec<(&Span, Extent)>,
+) -> Extent {
+    let mut pos = start;
+    let mut extents = Vec::new();
+    for (span, extent) in children {
+        if span.start > pos {
+            extents.push(Extent::Leaf(span.start - pos));
+        }
+        pos = pos.max(span.end);
+        extents.push(extent);
+    }
+    if end > pos {
+        extents.push(Extent::Leaf(end - pos));
+    }
+    Extent::Branch(label, extents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    struct Bytes(usize);
+
+    impl InfoSink for Bytes {
+        type Out = ();
+        fn sink<I: InfoSource>(&mut self, src: &mut I) {
+            for _ in 0..self.0 {
+                src.draw_u8();
+            }
+        }
+    }
+
+    // Draws a tag byte, then a pair of three byte values.
+    struct Pair;
+
+    impl InfoSink for Pair {
+        type Out = ();
+        fn sink<I: InfoSource>(&mut self, src: &mut I) {
+            src.draw_u8();
+            src.draw(Bytes(3));
+            src.draw(Bytes(3));
+        }
+    }
+
+    fn recorded<S: InfoSink>(sink: S) -> InfoPool {
+        let mut rec = InfoRecorder::new(RngSource::of(StdRng::seed_from_u64(0)));
+        rec.draw(sink);
+        rec.into_pool()
+    }
+
+    fn branch(label: &'static str, children: Vec<Extent>) -> Extent {
+        Extent::Branch(Some(Label::Borrowed(label)), children)
+    }
+
+    #[test]
+    fn should_show_nested_draws_as_branches() {
+        use self::Extent::*;
+        assert_eq!(
+            recorded(Pair).span_tree(),
+            Branch(
+                None,
+                vec![branch(
+                    "()",
+                    vec![
+                        Leaf(1),
+                        branch("()", vec![Leaf(3)]),
+                        branch("()", vec![Leaf(3)])
+                    ]
+                )]
+            )
+        );
+    }
+
+    #[test]
+    fn should_label_branches() {
+        use self::Extent::*;
+        let mut rec = InfoRecorder::new(RngSource::of(StdRng::seed_from_u64(0)));
+        rec.draw_labelled("pair", Pair);
+        match rec.into_pool().span_tree() {
+            Branch(None, ref roots) => match roots[..] {
+                [Branch(ref label, _)] => assert_eq!(label.as_ref().map(|l| &**l), Some("pair")),
+                ref other => panic!("Unexpected roots: {:?}", other),
+            },
+            other => panic!("Unexpected tree: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn should_include_bytes_outside_of_spans_as_leaves() {
+        use self::Extent::*;
+        let mut rec = InfoRecorder::new(RngSource::of(StdRng::seed_from_u64(0)));
+        rec.draw_u8();
+        rec.draw(Bytes(0));
+        rec.draw(Bytes(2));
+        rec.draw_u8();
+        assert_eq!(
+            rec.into_pool().span_tree(),
+            Branch(
+                None,
+                vec![
+                    Leaf(1),
+                    branch("()", vec![]),
+                    branch("()", vec![Leaf(2)]),
+                    Leaf(1)
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn unrecorded_pool_should_be_a_single_leaf() {
+        use self::Extent::*;
+        let pool = InfoPool::of_vec(vec![1, 2, 3]);
+        assert_eq!(pool.span_tree(), Branch(None, vec![Leaf(3)]));
+    }
+
+    #[test]
+    fn tree_should_cover_whole_pool() {
+        let pool = recorded(Pair);
+        assert_eq!(pool.span_tree().len(), pool.buffer().len());
+    }
+}
@@ -3,9 +3,60 @@
 //! random bytes that can be observed via the `InfoTap` object (obtained via
 //! `InfoPool#tap`).
 //!
-//! Also manages the shrinking process (see [`minimize`](fn.minimize.html)).
+//! Also manages the shrinking process (see [`minimize`](fn.minimize.html)),
+//! and reporting on its progress (see [`Observer`](trait.Observer.html)).
+//! The structure of the data drawn from a pool is available via
+//! [`InfoPool::span_tree`](struct.InfoPool.html#method.span_tree).
+//!
+//! # Encoding
+//!
+//! Pools can be saved and restored via a stable, versioned encoding, as
+//! either text or bytes. Drawing `optional(u16s())` from a pool
+//! holding the bytes `80 0b 0c` records a pool whose text encoding is:
+//!
+//! ```text
+//! suppositions-pool 1
+//! data 800b0c
+//! span 0 1 1 bool
+//! dependent 1 3 1 u16
+//! span 0 3 0 Option<u16>
+//! ```
+//!
+//! The first line names the format and version. The `data` line holds
+//! the pool's bytes as lower case hex. Each `span` line gives the start
+//! offset, end offset (exclusive), nesting level and (optionally) label of
+//! a span recorded whilst generating from the pool, in the order that they
+//! were recorded (ie: each span follows any spans nested within it). The
+//! label takes up the rest of the line, so may contain spaces; any
+//! backslashes and line breaks within it are escaped as `\\`, `\n` and
+//! `\r`. Generators supply their own labels (see
+//! [`Generator::label`](../generators/trait.Generator.html#method.label));
+//! labels that default to the name of a type may differ between compiler
+//! versions. Spans that were only drawn because of the value drawn by the
+//! previous span at the same level (see
+//! [`InfoSource::draw_dependent`](trait.InfoSource.html#method.draw_dependent))
+//! start with `dependent` rather than `span`.
+//!
+//! The binary encoding holds the same information: the magic bytes `SPPL`,
+//! the version, the length of the data followed by the data itself, then
+//! the number of spans followed by each span's start, end, level, whether
+//! it is dependent (as zero or one) and label.
+//! Labels are written as zero when absent, or otherwise as one more than
+//! their length followed by their UTF-8 bytes. All numbers are unsigned
+//! LEB128 varints.
+//!
+//! Decoding an encoded pool results in an equal pool. We reject versions
+//! that we do not understand, and spans that lie outside of the data. See
+//! [`InfoPool::to_text`](struct.InfoPool.html#method.to_text) and
+//! [`InfoPool::to_bytes`](struct.InfoPool.html#method.to_bytes).
 
+mod encoding;
+mod extent;
+mod observer;
 mod shrinkers;
 mod source;
+pub use self::encoding::*;
+pub use self::extent::*;
+pub use self::observer::*;
 pub use self::shrinkers::*;
 pub use self::source::*;
@@ -0,0 +1,49 @@
+use data::source::*;
+
+/// Something notable that happens whilst we test a property, or shrink a
+/// failing example. Each event carries the pool that it concerns.
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'a> {
+    /// We generated a new test case from this pool.
+    CaseGenerated(&'a InfoPool),
+    /// The subject passed for this test case.
+    CasePassed(&'a InfoPool),
+    /// One of the generators asked us to skip this test case.
+    CaseSkipped(&'a InfoPool),
+    /// The subject failed for this test case.
+    FailureFound(&'a InfoPool),
+    /// Whilst shrinking, we tried this candidate.
+    CandidateTried(&'a InfoPool),
+    /// The candidate still satisfied the predicate, and so becomes the
+    /// smallest example we know of. Carries the pool as it was replayed,
+    /// which may differ from the candidate tried.
+    CandidateAccepted(&'a InfoPool),
+    /// We could not shrink any further; carries the minimal pool.
+    ShrinkFinished(&'a InfoPool),
+}
+
+/// Receives [`Event`](enum.Event.html)s as a property is tested (see
+/// [`Property::with_observer`](../struct.Property.html#method.with_observer))
+/// or a pool is shrunk (see [`minimize_observed`](fn.minimize_observed.html)).
+/// Useful for progress reporting, metrics, or custom logging.
+pub trait Observer {
+    /// Called as each event happens.
+    fn observe(&mut self, event: &Event);
+}
+
+/// Ignores all events.
+impl Observer for () {
+    fn observe(&mut self, _: &Event) {}
+}
+
+impl<O: Observer + ?Sized> Observer for &mut O {
+    fn observe(&mut self, event: &Event) {
+        (**self).observe(event)
+    }
+}
+
+impl<O: Observer + ?Sized> Observer for Box<O> {
+    fn observe(&mut self, event: &Event) {
+        (**self).observe(event)
+    }
+}
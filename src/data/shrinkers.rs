@@ -1,6 +1,9 @@
+use data::observer::*;
 use data::source::*;
+use rand::Rng;
 use std::cmp::min;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::iter;
 
 /// Iterates over a series of shrunk pools. If we imagine that our buffer has
 /// a sz of (1 << (log2sz-1)) < sz ≤ (1 << log2sz), then where:
@@ -28,6 +31,35 @@ struct RemovalShrinker<I> {
     segments: I,
 }
 
+/// Overwrites each span with zeroes in place, and removes the data for any
+/// dependent span that it controls (see [`zeroing_units`](fn.zeroing_units.html)).
+/// Unlike removal, this leaves the data drawn after the span where it was,
+/// so later draws still line up.
+#[derive(Debug)]
+struct ZeroingShrinker<I> {
+    seed: InfoPool,
+    segments: I,
+}
+
+/// Replaces the data drawn for a span with the data drawn for one of its
+/// descendants with the same label, eg: turning `Add(Add(a, b), c)` into `a`.
+#[derive(Debug)]
+struct HoistingShrinker {
+    seed: InfoPool,
+    hoists: ::std::vec::IntoIter<(Span, Span)>,
+}
+
+/// Reorders sibling spans (ie: those drawn directly by the same parent) with
+/// the same label, such as the elements of a list. We try sorting each group
+/// of siblings by their contents, and then swapping each adjacent pair that
+/// is out of order.
+#[derive(Debug)]
+struct SiblingShrinker {
+    seed: InfoPool,
+    // The spans to fill, and the spans whose contents we fill them with.
+    moves: ::std::vec::IntoIter<(Vec<Span>, Vec<Span>)>,
+}
+
 #[derive(Debug)]
 struct ScalarShrinker {
     seed: InfoPool,
@@ -44,6 +76,295 @@ impl ScalarShrinker {
         }
     }
 }
+impl HoistingShrinker {
+    fn new(seed: InfoPool) -> Self {
+        let mut hoists = Vec::new();
+        // We start with the outermost spans, as they make for the biggest
+        // reductions.
+        for (i, outer) in seed.spans.iter().enumerate().rev() {
+            if outer.label.is_none() {
+                continue;
+            }
+            for inner in seed.descendants(i).iter().rev() {
+                let smaller = inner.end - inner.start < outer.end - outer.start;
+                if inner.label == outer.label && smaller {
+                    hoists.push((outer.clone(), inner.clone()));
+                }
+            }
+        }
+        let hoists = hoists.into_iter();
+        HoistingShrinker { seed, hoists }
+    }
+}
+
+impl Iterator for HoistingShrinker {
+    type Item = InfoPool;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.hoists.next().map(|(outer, inner)| {
+            let mut candidate = InfoPool::new();
+            candidate.data.extend(&self.seed.data[outer.before()]);
+            candidate
+                .data
+                .extend(&self.seed.data[inner.start..inner.end]);
+            candidate.data.extend(&self.seed.data[outer.after()]);
+            debug!("hoisted {:?} into {:?}", inner, outer);
+            trace!("candidate {:?}", candidate);
+            candidate
+        })
+    }
+}
+
+impl SiblingShrinker {
+    fn new(seed: InfoPool) -> Self {
+        let mut moves = Vec::new();
+        for siblings in sibling_groups(&seed) {
+            let contents = |span: &Span| &seed.data[span.start..span.end];
+            let mut sorted = siblings.clone();
+            sorted.sort_by(|a, b| contents(a).cmp(contents(b)));
+            if sorted != siblings {
+                moves.push((siblings.clone(), sorted));
+            }
+            for i in 1..siblings.len() {
+                if contents(&siblings[i]) < contents(&siblings[i - 1]) {
+                    let mut swapped = siblings.clone();
+                    swapped.swap(i - 1, i);
+                    moves.push((siblings.clone(), swapped));
+                }
+            }
+        }
+        let moves = moves.into_iter();
+        SiblingShrinker { seed, moves }
+    }
+}
+
+/// Groups the spans drawn directly by each parent span (or at the top
+/// level) by label, in the order that they were drawn. Only returns groups
+/// with more than one span.
+fn sibling_groups(pool: &InfoPool) -> Vec<Vec<Span>> {
+    let mut groups = Vec::new();
+    let parents = (0..pool.spans.len()).map(Some).chain(iter::once(None));
+    for parent in parents {
+        let mut by_label = BTreeMap::new();
+        for child in pool.children(parent) {
+            let child = &pool.spans[child];
+            by_label
+                .entry(child.label.clone())
+                .or_insert_with(Vec::new)
+                .push(child.clone());
+        }
+        groups.extend(by_label.into_values().filter(|g| g.len() > 1));
+    }
+    groups
+}
+
+impl Iterator for SiblingShrinker {
+    type Item = InfoPool;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.moves.next().map(|(slots, sources)| {
+            let mut candidate = InfoPool::new();
+            let mut pos = 0;
+            for (slot, source) in slots.iter().zip(sources.iter()) {
+                candidate.data.extend(&self.seed.data[pos..slot.start]);
+                candidate
+                    .data
+                    .extend(&self.seed.data[source.start..source.end]);
+                pos = slot.end;
+            }
+            candidate.data.extend(&self.seed.data[pos..]);
+            debug!("reordered {:?} as {:?}", slots, sources);
+            trace!("candidate {:?}", candidate);
+            candidate
+        })
+    }
+}
+
+/// The widest span that we will treat as an integer.
+const MAX_INTEGER_BYTES: usize = 16;
+
+/// Returns the leaf spans (ie: those with no nested spans) that are narrow
+/// enough to treat as integers, in the order that they were drawn.
+fn integer_leaves(pool: &InfoPool) -> Vec<&Span> {
+    pool.spans
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| pool.descendants(i).is_empty())
+        .map(|(_, span)| span)
+        .filter(|span| (1..=MAX_INTEGER_BYTES).contains(&(span.end - span.start)))
+        .collect()
+}
+
+/// Reads the bytes of `span` as a big-endian integer.
+fn span_integer(pool: &InfoPool, span: &Span) -> u128 {
+    pool.data[span.start..span.end]
+        .iter()
+        .fold(0u128, |acc, &b| (acc << 8) | b as u128)
+}
+
+/// Overwrites the bytes of `span` with `val` as a big-endian integer.
+fn set_span_integer(pool: &mut InfoPool, span: &Span, val: u128) {
+    let bytes = val.to_be_bytes();
+    let width = span.end - span.start;
+    pool.data[span.start..span.end].copy_from_slice(&bytes[bytes.len() - width..]);
+}
+
+/// The largest integer that will fit in `span`.
+fn span_max(span: &Span) -> u128 {
+    u128::MAX >> (128 - 8 * (span.end - span.start))
+}
+
+/// Binary searches for the smallest value below `orig` for which
+/// `attempt` returns a pool, assuming that `orig` itself would. Returns the
+/// pool for the smallest value found, if any.
+fn binary_search<F: FnMut(u128) -> Option<InfoPool>>(
+    orig: u128,
+    mut attempt: F,
+) -> Option<InfoPool> {
+    // We know that `hi` is accepted, and that nothing below `lo` is.
+    let (mut lo, mut hi) = (0, orig);
+    let mut best = None;
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match attempt(mid) {
+            Some(accepted) => {
+                hi = mid;
+                best = Some(accepted);
+            }
+            None => lo = mid + 1,
+        }
+    }
+    best
+}
+
+/// Treats the bytes of each leaf span (ie: one with no nested spans) as a
+/// single big-endian integer, such as those drawn by
+/// [`u64s`](../generators/fn.u64s.html), and binary searches for the
+/// smallest value that `test` accepts. Returns the first improvement found.
+fn shrink_integers<F: FnMut(InfoPool) -> Option<InfoPool>>(
+    seed: &InfoPool,
+    test: &mut F,
+) -> Option<InfoPool> {
+    // Single bytes are left to `ScalarShrinker`.
+    let leaves = integer_leaves(seed);
+    for span in leaves.into_iter().filter(|s| s.end - s.start > 1) {
+        let orig_val = span_integer(seed, span);
+        let best = binary_search(orig_val, |val| {
+            let mut candidate = seed.clone();
+            set_span_integer(&mut candidate, span, val);
+            debug!("shrunk integer {:?} {}->{}", span, orig_val, val);
+            test(candidate)
+        });
+        if best.is_some() {
+            return best;
+        }
+    }
+    None
+}
+
+/// Finds leaf spans with the same (non-zero) contents, such as the `a` and
+/// `b` in a failure that needs `a == b`, and lowers them together. Returns
+/// the first improvement found.
+fn shrink_duplicates<F: FnMut(InfoPool) -> Option<InfoPool>>(
+    seed: &InfoPool,
+    test: &mut F,
+) -> Option<InfoPool> {
+    let mut duplicates = BTreeMap::new();
+    for span in integer_leaves(seed) {
+        let contents = &seed.data[span.start..span.end];
+        if contents.iter().any(|&b| b != 0) {
+            duplicates
+                .entry(contents)
+                .or_insert_with(Vec::new)
+                .push(span);
+        }
+    }
+    for spans in duplicates.values().filter(|spans| spans.len() > 1) {
+        let orig_val = span_integer(seed, spans[0]);
+        let best = binary_search(orig_val, |val| {
+            let mut candidate = seed.clone();
+            for span in spans.iter() {
+                set_span_integer(&mut candidate, span, val);
+            }
+            debug!("shrunk duplicates {:?} {}->{}", spans, orig_val, val);
+            test(candidate)
+        });
+        if best.is_some() {
+            return best;
+        }
+    }
+    None
+}
+
+/// For each pair of adjacent leaf spans of the same width, lowers the first
+/// whilst raising the second by the same amount, so that failures that
+/// depend on eg: the sum of two values can still shrink. Returns the first
+/// improvement found.
+fn redistribute<F: FnMut(InfoPool) -> Option<InfoPool>>(
+    seed: &InfoPool,
+    test: &mut F,
+) -> Option<InfoPool> {
+    let leaves = integer_leaves(seed);
+    for pair in leaves.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if a.end - a.start != b.end - b.start {
+            continue;
+        }
+        let (orig_a, orig_b) = (span_integer(seed, a), span_integer(seed, b));
+        let best = binary_search(orig_a, |val| {
+            let new_b = match orig_b.checked_add(orig_a - val) {
+                Some(new_b) if new_b <= span_max(b) => new_b,
+                _ => return None,
+            };
+            let mut candidate = seed.clone();
+            set_span_integer(&mut candidate, a, val);
+            set_span_integer(&mut candidate, b, new_b);
+            debug!(
+                "redistributed {:?}, {:?} ({}, {})->({}, {})",
+                a, b, orig_a, orig_b, val, new_b
+            );
+            test(candidate)
+        });
+        if best.is_some() {
+            return best;
+        }
+    }
+    None
+}
+
+/// Sweeps once over the bytes of `seed`, trying to delete each one, and
+/// otherwise to lower it to each smaller value in turn. We keep each
+/// improvement and carry on from the next byte, rather than starting over.
+/// Returns the result if it is any smaller than `seed`.
+fn normalize<F: FnMut(&InfoPool, InfoPool) -> Option<InfoPool>>(
+    seed: &InfoPool,
+    test: &mut F,
+) -> Option<InfoPool> {
+    let mut best = seed.clone();
+    let mut pos = 0;
+    while pos < best.data.len() {
+        let mut deleted = best.clone();
+        deleted.data.remove(pos);
+        if let Some(accepted) = test(&best, deleted) {
+            best = accepted;
+            continue;
+        }
+        for val in 0..best.data[pos] {
+            let mut candidate = best.clone();
+            candidate.data[pos] = val;
+            trace!("normalized candidate {:?}", candidate);
+            if let Some(accepted) = test(&best, candidate) {
+                best = accepted;
+                break;
+            }
+        }
+        pos += 1;
+    }
+    if shortlex_lt(&best, seed) {
+        Some(best)
+    } else {
+        None
+    }
+}
+
 impl Iterator for ScalarShrinker {
     type Item = InfoPool;
     fn next(&mut self) -> Option<Self::Item> {
@@ -80,6 +401,51 @@ impl Iterator for ScalarShrinker {
     }
 }
 
+/// Produces an endless stream of random variations on a pool, by nudging
+/// individual bytes up or down in the same way that `ScalarShrinker` nudges
+/// them down. Used for hill-climbing towards higher
+/// [`target`](../fn.target.html) scores.
+#[derive(Debug)]
+pub(crate) struct ScalarMutator<R> {
+    seed: InfoPool,
+    rng: R,
+}
+
+impl<R: Rng> ScalarMutator<R> {
+    pub(crate) fn new(pool: InfoPool, rng: R) -> Self {
+        ScalarMutator { seed: pool, rng }
+    }
+}
+
+impl<R: Rng> Iterator for ScalarMutator<R> {
+    type Item = InfoPool;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut candidate = self.seed.clone();
+        if candidate.data.is_empty() {
+            candidate.data.push(self.rng.gen());
+            trace!("candidate {:?}", candidate);
+            return Some(candidate);
+        }
+
+        let pos = self.rng.gen_range(0..candidate.data.len());
+        let bitoff = self.rng.gen_range(0..8u32);
+        let orig_val = candidate.data[pos];
+        let new_val = if self.rng.gen() {
+            orig_val + ((0xff - orig_val) >> bitoff)
+        } else {
+            orig_val - (orig_val >> bitoff)
+        };
+
+        candidate.data[pos] = new_val;
+        debug!(
+            "mutated item (bitoff:{}) {} {}->{}",
+            bitoff, pos, orig_val, new_val
+        );
+        trace!("candidate {:?}", candidate);
+        Some(candidate)
+    }
+}
+
 /// Try to find the smallest pool `p` such that the predicate `pred` returns
 /// true. Given that our [generators](../generators/index.html) tend to
 /// generate smaller outputs from smaller inputs, by minimizing the source
@@ -88,72 +454,147 @@ impl Iterator for ScalarShrinker {
 /// If we do not find any smaller pool that satisifies `pred`; we return
 /// `None`.
 ///
-/// Currently, we have two heuristics for shrinking: removing slices, and
-/// reducing individual values.
+/// Currently, we have four heuristics for shrinking: hoisting subterms,
+/// removing slices, reordering siblings, and reducing individual values
+/// (both whole integers and single bytes).
+///
+/// Hoisting replaces the data drawn for a value with the data drawn for a
+/// nested value of the same type (or more precisely, of the same
+/// [label](trait.InfoSource.html#method.draw_labelled)). So a recursive
+/// value like `Add(Add(a, b), c)` can shrink to `a` in a single step.
 ///
 /// Removing slices tries to remove as much of the pool as it can whilst still
 /// having the predicate hold. At present, we do this by removing each half
-/// and testing, then each quarter, eighths, and so on. Eventually, we plan to
-/// track the regions of data that each generator draws from, and use that to
-/// optimise the shrinking process.
+/// and testing, then each quarter, eighths, and so on. We also try removing
+/// the data drawn by each generator, or overwriting it with zeroes, which
+/// leaves the data for any later values where they were.
+///
+/// Reordering sorts the values drawn by sibling generators of the same type,
+/// such as the elements of a list, or failing that, swaps adjacent pairs.
 ///
 /// Reducing individual values basically goes through each position in the
 /// pool, and then tries reducing it to zero, then half, thn three quarters,
-/// seven eighths, and so on.
+/// seven eighths, and so on. Before that, we binary search the data drawn
+/// by each innermost generator, treated as a single big-endian integer, so
+/// that multi-byte numbers shrink as a unit. We also lower equal values
+/// together, and try moving value from one to the next, so that failures
+/// that depend on eg: `a == b` or `a + b` can still shrink.
+///
+/// # Normalization
+///
+/// We only accept a candidate if the data that the predicate draws from it
+/// is smaller than the best found so far, in shortlex order: shorter pools
+/// first, and then lexicographically smaller ones. Once none of the
+/// heuristics makes progress, we make one final sweep over the pool, trying
+/// to delete each byte and to lower each byte to every smaller value. So the
+/// result is usually locally minimal, which means equivalent failures
+/// usually shrink to identical pools, regardless of where they started
+/// from.
 pub fn minimize<F: Fn(&mut InfoRecorder<InfoReplay>) -> bool>(
     orig: &InfoPool,
     pred: &F,
 ) -> InfoPool {
-    let mut best = orig.clone();
+    minimize_observed(orig, pred, &mut ())
+}
+
+/// Like [`minimize`](fn.minimize.html), but reports each candidate tried
+/// and accepted to `observer`.
+pub fn minimize_observed<F: Fn(&mut InfoRecorder<InfoReplay>) -> bool, O: Observer>(
+    orig: &InfoPool,
+    pred: &F,
+    observer: &mut O,
+) -> InfoPool {
+    // We compare candidates against the data that the predicate actually
+    // draws, so start from what it draws from the original pool.
+    let mut best = {
+        let mut recorder = InfoRecorder::new(orig.replay());
+        if pred(&mut recorder) {
+            recorder.into_pool()
+        } else {
+            orig.clone()
+        }
+    };
     // this might be better as something that we can apply a window to,
     // or bloom filter.
+    // The final normalization sweep never repeats a candidate, so we don't
+    // remember those.
     let mut seen = HashSet::new();
+    let mut test = |best: &InfoPool, c: InfoPool, remember: bool| {
+        if remember {
+            if seen.contains(&c) {
+                debug!("Skipping seen item");
+                return None;
+            }
+            seen.insert(c.clone());
+        }
+
+        observer.observe(&Event::CandidateTried(&c));
+        let mut recorder = InfoRecorder::new(c.replay());
+        let test = pred(&mut recorder);
+        trace!("test result: {:?} <= {:?}", test, c);
+        // Extract the execution trace from the pool at this point. This may
+        // differ from the candidate, eg: removing a byte can shift later ones
+        // into more significant positions. So we only accept pools that are
+        // smaller than the best so far, which ensures that we make progress.
+        let accepted = recorder.into_pool();
+        if test && shortlex_lt(&accepted, best) {
+            observer.observe(&Event::CandidateAccepted(&accepted));
+            Some(accepted)
+        } else {
+            None
+        }
+    };
 
     loop {
         debug!("Shrinking pool");
-        debug!("Seen {:?} pools", seen.len());
         trace!("Pool: {:?}", best);
 
+        let hoists = HoistingShrinker::new(best.clone());
         let interval_removals = RemovalShrinker::remove_recorded_intervals(best.clone());
         let delta_removals = RemovalShrinker::delta_debug_of_pool(best.clone());
+        let interval_zeroes = ZeroingShrinker::zero_recorded_intervals(best.clone());
+        let siblings = SiblingShrinker::new(best.clone());
+        let removals = hoists
+            .chain(interval_removals)
+            .chain(interval_zeroes)
+            .chain(delta_removals)
+            .chain(siblings);
         let scalars = ScalarShrinker::new(best.clone());
-        let shrunk_pools = interval_removals.chain(delta_removals).chain(scalars);
 
-        {
-            let mut matching_shrinks = shrunk_pools.filter_map(|c| {
-                if seen.contains(&c) {
-                    debug!("Skipping seen item");
-                    return None;
-                }
-                seen.insert(c.clone());
-
-                let mut recorder = InfoRecorder::new(c.replay());
-                let test = pred(&mut recorder);
-                trace!("test result: {:?} <= {:?}", test, c);
-                // Extract the execution trace from the pool at this point.
-                if test {
-                    Some(recorder.into_pool())
-                } else {
-                    None
-                }
-            });
+        let shrunk = removals
+            .filter_map(|c| test(&best, c, true))
+            .next()
+            .or_else(|| shrink_integers(&best, &mut |c| test(&best, c, true)))
+            .or_else(|| shrink_duplicates(&best, &mut |c| test(&best, c, true)))
+            .or_else(|| redistribute(&best, &mut |c| test(&best, c, true)));
+        let shrunk = shrunk.or_else(|| scalars.filter_map(|c| test(&best, c, true)).next());
 
-            if let Some(candidate) = matching_shrinks.next() {
-                debug!("Re-Shrinking");
-                best = candidate;
-            } else {
-                debug!("Nothing smaller found");
-                trace!("... than {:?}", best);
-                break;
-            }
+        if let Some(candidate) = shrunk {
+            debug!("Re-Shrinking");
+            best = candidate;
+        } else {
+            debug!("Nothing smaller found");
+            trace!("... than {:?}", best);
+            break;
         }
 
         trace!("Note best: {:?}", best);
     }
 
+    if let Some(normalized) = normalize(&best, &mut |b, c| test(b, c, false)) {
+        debug!("Normalized");
+        best = normalized;
+    }
+
+    observer.observe(&Event::ShrinkFinished(&best));
     best
 }
 
+/// Orders pools by length, and then lexicographically.
+fn shortlex_lt(a: &InfoPool, b: &InfoPool) -> bool {
+    (a.data.len(), &a.data) < (b.data.len(), &b.data)
+}
+
 fn ulog2(val: usize) -> usize {
     let max_pow = 0usize.count_zeros() as usize;
     max_pow - val.leading_zeros() as usize
@@ -213,13 +654,73 @@ impl<I> RemovalShrinker<I> {
     }
 }
 
-impl RemovalShrinker<InfoPoolIntervalsIter> {
+impl RemovalShrinker<::std::vec::IntoIter<Span>> {
     fn remove_recorded_intervals(seed: InfoPool) -> Self {
-        let segments = seed.spans_iter();
+        let segments = removal_units(&seed).into_iter();
         RemovalShrinker::new(seed, segments)
     }
 }
 
+/// Returns the recorded spans that we can usefully remove, outermost first.
+/// If we only drew a span because of the value drawn by its previous
+/// sibling (see [`InfoSource::draw_dependent`](trait.InfoSource.html#method.draw_dependent)),
+/// then removing either one on its own would just shift the data for one
+/// into the other. So we remove them together, as a single unit, and
+/// never remove the controlling span (or anything within it) on its own.
+fn removal_units(pool: &InfoPool) -> Vec<Span> {
+    let controllers = controllers(pool);
+    let mut units = Vec::new();
+    for (i, span) in pool.spans.iter().enumerate().rev() {
+        if span.dependent {
+            match pool.previous_sibling(i) {
+                Some(c) => units.push(Span::of_pair((c.start, span.end))),
+                None => units.push(span.clone()),
+            }
+        } else if !within_any(&controllers, span) {
+            units.push(span.clone());
+        }
+    }
+    units
+}
+
+/// Returns the recorded spans that we can usefully zero, outermost first,
+/// along with the offset at which the data after each one resumes. Zeroing
+/// a span that controls a dependent span usually means that we no longer
+/// draw the dependent one (eg: an `optional` flag), so we remove the data
+/// for that along with it, which keeps later draws lined up. As with
+/// [`removal_units`](fn.removal_units.html), we never zero anything within
+/// a controlling span on its own.
+fn zeroing_units(pool: &InfoPool) -> Vec<(Span, usize)> {
+    let controllers = controllers(pool);
+    let mut units = Vec::new();
+    for (i, span) in pool.spans.iter().enumerate().rev() {
+        if span.dependent {
+            if let Some(c) = pool.previous_sibling(i) {
+                units.push((c.clone(), span.end));
+            }
+            units.push((span.clone(), span.end));
+        } else if !within_any(&controllers, span) {
+            units.push((span.clone(), span.end));
+        }
+    }
+    units
+}
+
+/// The spans that control whether (or how) we draw the dependent span that
+/// follows them.
+fn controllers(pool: &InfoPool) -> Vec<&Span> {
+    (0..pool.spans.len())
+        .filter(|&i| pool.spans[i].dependent)
+        .filter_map(|i| pool.previous_sibling(i))
+        .collect()
+}
+
+fn within_any(outer: &[&Span], span: &Span) -> bool {
+    outer
+        .iter()
+        .any(|c| c.start <= span.start && span.end <= c.end && c.level <= span.level)
+}
+
 impl RemovalShrinker<DeltaDebugSegmentIterator> {
     fn delta_debug_of_pool(seed: InfoPool) -> Self {
         let len = seed.data.len();
@@ -245,11 +746,42 @@ impl<I: Iterator<Item = Span>> Iterator for RemovalShrinker<I> {
         })
     }
 }
+
+impl ZeroingShrinker<::std::vec::IntoIter<(Span, usize)>> {
+    fn zero_recorded_intervals(seed: InfoPool) -> Self {
+        let segments = zeroing_units(&seed).into_iter();
+        ZeroingShrinker { seed, segments }
+    }
+}
+
+impl<I: Iterator<Item = (Span, usize)>> Iterator for ZeroingShrinker<I> {
+    type Item = InfoPool;
+    fn next(&mut self) -> Option<Self::Item> {
+        // Spans that are already zero would just give us the seed back.
+        let seed = &self.seed;
+        self.segments
+            .by_ref()
+            .find(|&(ref span, resume)| {
+                resume > span.end || seed.data[span.start..span.end].iter().any(|&b| b != 0)
+            })
+            .map(|(span, resume)| {
+                let mut candidate = seed.clone();
+                candidate.data.drain(span.end..resume);
+                for b in candidate.data[span.start..span.end].iter_mut() {
+                    *b = 0;
+                }
+                debug!("zeroed {:?}, resuming from {}", span, resume);
+                trace!("candidate {:?}", candidate);
+
+                candidate
+            })
+    }
+}
 #[cfg(test)]
 mod tests {
     extern crate env_logger;
     use super::*;
-    use std::collections::BTreeMap;
+    use std::collections::BTreeSet;
 
     // The end of the buffer is semantically equivalent to zero for generators,
     // so we can ignore those.
@@ -289,6 +821,40 @@ mod tests {
         assert_eq!(without_trailing_zeroes(min.buffer()), &[1, 1])
     }
 
+    #[derive(Debug, Default)]
+    struct Counts {
+        tried: usize,
+        accepted: usize,
+        finished: Vec<InfoPool>,
+    }
+
+    impl Observer for Counts {
+        fn observe(&mut self, event: &Event) {
+            match *event {
+                Event::CandidateTried(_) => self.tried += 1,
+                Event::CandidateAccepted(_) => self.accepted += 1,
+                Event::ShrinkFinished(p) => self.finished.push(p.clone()),
+                _ => (),
+            }
+        }
+    }
+
+    #[test]
+    fn minimiser_should_report_progress_to_observer() {
+        env_logger::try_init().unwrap_or_default();
+        let p = InfoPool::of_vec(vec![255; 3]);
+        let mut counts = Counts::default();
+        let min = minimize_observed(
+            &p,
+            &|t| take_n(t, 16).into_iter().any(|v| v >= 13),
+            &mut counts,
+        );
+
+        assert!(counts.accepted > 0, "Accepted: {:?}", counts);
+        assert!(counts.tried > counts.accepted, "Tried: {:?}", counts);
+        assert_eq!(counts.finished, vec![min]);
+    }
+
     #[test]
     fn minimiser_should_minimise_scalar_values() {
         let p = InfoPool::of_vec(vec![255; 3]);
@@ -320,6 +886,127 @@ mod tests {
         assert_eq!(without_trailing_zeroes(min.buffer()), &[251])
     }
 
+    struct U16;
+
+    impl InfoSink for U16 {
+        type Out = u16;
+        fn sink<I: InfoSource>(&mut self, src: &mut I) -> u16 {
+            (src.draw_u8() as u16) << 8 | src.draw_u8() as u16
+        }
+    }
+
+    #[test]
+    fn minimiser_should_minimise_multi_byte_integers_as_a_unit() {
+        env_logger::try_init().unwrap_or_default();
+        let p = InfoPool::of_vec(vec![0x01, 0x01]);
+        // Shrinking byte by byte would get stuck at 0x0100.
+        let min = minimize(&p, &|t| t.draw(U16) >= 0xf1);
+
+        assert_eq!(min.buffer(), &[0x00, 0xf1])
+    }
+
+    struct U8;
+
+    impl InfoSink for U8 {
+        type Out = u8;
+        fn sink<I: InfoSource>(&mut self, src: &mut I) -> u8 {
+            src.draw_u8()
+        }
+    }
+
+    #[test]
+    fn minimiser_should_lower_duplicated_values_together() {
+        env_logger::try_init().unwrap_or_default();
+        let p = InfoPool::of_vec(vec![200, 0, 200]);
+        // Lowering either value on its own would make them differ.
+        let min = minimize(&p, &|t| {
+            let (a, _, c) = (t.draw(U8), t.draw(U8), t.draw(U8));
+            a == c && a >= 10
+        });
+
+        assert_eq!(min.buffer(), &[10, 0, 10])
+    }
+
+    #[test]
+    fn minimiser_should_redistribute_between_neighbouring_values() {
+        env_logger::try_init().unwrap_or_default();
+        let p = InfoPool::of_vec(vec![200, 200]);
+        // Lowering either value on its own would make the sum too small.
+        let min = minimize(&p, &|t| t.draw(U8) as usize + t.draw(U8) as usize >= 300);
+
+        assert_eq!(min.buffer(), &[45, 255])
+    }
+
+    struct U128;
+
+    impl InfoSink for U128 {
+        type Out = u128;
+        fn sink<I: InfoSource>(&mut self, src: &mut I) -> u128 {
+            (0..16).fold(0, |acc, _| (acc << 8) | src.draw_u8() as u128)
+        }
+    }
+
+    #[test]
+    fn minimiser_should_not_overflow_when_redistributing_widest_integers() {
+        env_logger::try_init().unwrap_or_default();
+        let mut data = vec![1; 16];
+        data.extend(vec![0xff; 16]);
+        let p = InfoPool::of_vec(data);
+        let min = minimize(&p, &|t| {
+            let (a, b) = (t.draw(U128), t.draw(U128));
+            a > 0 && b == u128::MAX
+        });
+
+        let mut expected = vec![0; 15];
+        expected.push(1);
+        expected.extend(vec![0xff; 16]);
+        assert_eq!(min.buffer(), &expected[..])
+    }
+
+    #[test]
+    fn minimiser_should_find_same_pool_from_different_starts() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+        env_logger::try_init().unwrap_or_default();
+        // Halving won't find these, as most smaller values do not fail.
+        let pred = |t: &mut InfoRecorder<InfoReplay>| {
+            let vals = take_n(t, 2);
+            vals[0] % 10 == 7 && vals[1] % 10 == 3
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut minimal = BTreeSet::new();
+        let mut starts = 0;
+        while starts < 20 {
+            let p = InfoPool::of_vec((0..2).map(|_| rng.gen()).collect());
+            if pred(&mut InfoRecorder::new(p.replay())) {
+                minimal.insert(minimize(&p, &pred).buffer().to_vec());
+                starts += 1;
+            }
+        }
+
+        assert_eq!(minimal, [vec![7, 3]].iter().cloned().collect());
+    }
+
+    #[test]
+    fn normalizing_should_delete_then_lower_each_byte_once() {
+        let p = InfoPool::of_vec(vec![1, 2]);
+        let mut tried = Vec::new();
+        let res = normalize(&p, &mut |_, c: InfoPool| {
+            tried.push(c.buffer().to_vec());
+            if c.buffer() == [1, 1] {
+                Some(c)
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(res.map(|p| p.buffer().to_vec()), Some(vec![1, 1]));
+        assert_eq!(
+            tried,
+            vec![vec![2], vec![0, 2], vec![1], vec![1, 0], vec![1, 1]]
+        );
+    }
+
     #[test]
     fn shrink_by_delta_debug_removal_should_produce_somewhat_unique_outputs() {
         env_logger::try_init().unwrap_or_default();
@@ -363,6 +1050,27 @@ mod tests {
         )
     }
 
+    #[test]
+    fn mutator_should_preserve_pool_length() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let p = InfoPool::of_vec((0..16usize).map(|v| (v * 16) as u8).collect::<Vec<_>>());
+        let mutator = ScalarMutator::new(p.clone(), StdRng::seed_from_u64(0));
+        for val in mutator.take(256) {
+            assert_eq!(val.buffer().len(), p.buffer().len());
+        }
+    }
+
+    #[test]
+    fn mutator_should_usually_produce_different_pools() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let p = InfoPool::of_vec(vec![0x80; 4]);
+        let mutator = ScalarMutator::new(p.clone(), StdRng::seed_from_u64(0));
+        let differing = mutator.take(256).filter(|val| val != &p).count();
+        assert!(differing > 128, "Differing items:{} > 128", differing);
+    }
+
     #[test]
     fn delta_debug_segments_should_generate_segments_on_power_of_two_boundary() {
         use std::collections::BTreeSet;
@@ -411,6 +1119,110 @@ mod tests {
         );
     }
 
+    #[test]
+    fn shrink_by_hoisting_should_replace_spans_with_descendants_of_same_label() {
+        let span = |start, end, level, label| Span {
+            start,
+            end,
+            level,
+            label: Some(Label::Borrowed(label)),
+            ..Span::default()
+        };
+        let p = InfoPool {
+            data: vec![0, 1, 2, 3, 4],
+            spans: vec![
+                span(1, 3, 1, "expr"),
+                span(3, 4, 1, "lit"),
+                span(0, 4, 0, "expr"),
+            ],
+        };
+        let vals = HoistingShrinker::new(p).collect::<Vec<_>>();
+
+        assert_eq!(vals, vec![InfoPool::of_vec(vec![1, 2, 4])]);
+    }
+
+    #[test]
+    fn shrink_by_reordering_should_sort_then_swap_siblings_of_same_label() {
+        let span = |start, level, label| Span {
+            start,
+            end: start + 1,
+            level,
+            label: Some(Label::Borrowed(label)),
+            ..Span::default()
+        };
+        let p = InfoPool {
+            data: vec![9, 5, 0, 7],
+            spans: vec![
+                span(0, 0, "u8"),
+                span(1, 0, "u8"),
+                span(2, 0, "bool"),
+                span(3, 0, "u8"),
+            ],
+        };
+        let vals = SiblingShrinker::new(p)
+            .map(|p| p.buffer().to_vec())
+            .collect::<Vec<_>>();
+
+        assert_eq!(vals, vec![vec![5, 7, 0, 9], vec![5, 9, 0, 7]]);
+    }
+
+    #[test]
+    fn sibling_groups_should_only_include_direct_children() {
+        let span = |start, end, level| Span {
+            start,
+            end,
+            level,
+            ..Span::default()
+        };
+        let p = InfoPool {
+            data: vec![0; 4],
+            spans: vec![
+                span(0, 1, 2),
+                span(1, 2, 2),
+                span(0, 2, 1),
+                span(2, 4, 1),
+                span(0, 4, 0),
+            ],
+        };
+        let groups = sibling_groups(&p)
+            .into_iter()
+            .map(|g| g.iter().map(Span::as_pair).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(groups, vec![vec![(0, 1), (1, 2)], vec![(0, 2), (2, 4)]]);
+    }
+
+    #[test]
+    fn removal_units_should_remove_dependent_spans_with_their_controllers() {
+        let span = |start, end, level, dependent| Span {
+            start,
+            end,
+            level,
+            dependent,
+            ..Span::default()
+        };
+        let p = InfoPool {
+            data: vec![0; 6],
+            spans: vec![
+                // A flag, containing a byte, and then the value it controls.
+                span(0, 1, 3, false),
+                span(0, 2, 2, false),
+                span(2, 4, 2, true),
+                span(0, 4, 1, false),
+                // A dependent span with no previous sibling.
+                span(4, 5, 2, true),
+                span(4, 6, 1, false),
+                span(0, 6, 0, false),
+            ],
+        };
+        let units = removal_units(&p)
+            .iter()
+            .map(Span::as_pair)
+            .collect::<Vec<_>>();
+
+        assert_eq!(units, vec![(0, 6), (4, 6), (4, 5), (0, 4), (0, 4)]);
+    }
+
     #[test]
     fn shrink_by_removal_should_remove_stated_slices() {
         env_logger::try_init().unwrap_or_default();
@@ -420,4 +1232,56 @@ mod tests {
 
         assert_eq!(vals, vec![InfoPool::of_vec(vec![0, 1, 3, 4])]);
     }
+
+    #[test]
+    fn shrink_by_zeroing_should_zero_stated_slices_in_place() {
+        env_logger::try_init().unwrap_or_default();
+        let p = InfoPool::of_vec(vec![0, 1, 2, 3, 4]);
+        let spans = vec![
+            (Span::of_pair((0, 1)), 1),
+            (Span::of_pair((1, 3)), 3),
+            (Span::of_pair((1, 2)), 3),
+        ];
+        let vals = ZeroingShrinker {
+            seed: p,
+            segments: spans.into_iter(),
+        }
+        .map(|p| p.buffer().to_vec())
+        .collect::<Vec<_>>();
+
+        // The first span is already zero, so is skipped.
+        assert_eq!(vals, vec![vec![0, 0, 0, 3, 4], vec![0, 0, 3, 4]]);
+    }
+
+    #[test]
+    fn zeroing_units_should_remove_dependent_spans_when_zeroing_their_controllers() {
+        let span = |start, end, level, dependent| Span {
+            start,
+            end,
+            level,
+            dependent,
+            ..Span::default()
+        };
+        let p = InfoPool {
+            data: vec![0; 5],
+            spans: vec![
+                // A flag, containing a byte, and then the value it controls.
+                span(0, 1, 2, false),
+                span(0, 2, 1, false),
+                span(2, 4, 1, true),
+                // Something drawn afterwards.
+                span(4, 5, 1, false),
+                span(0, 5, 0, false),
+            ],
+        };
+        let units = zeroing_units(&p)
+            .into_iter()
+            .map(|(span, resume)| (span.as_pair(), resume))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            units,
+            vec![((0, 5), 5), ((4, 5), 5), ((0, 2), 4), ((2, 4), 4)]
+        );
+    }
 }
@@ -1,15 +1,24 @@
 use hex_slice::AsHex;
 use rand::rngs::{OsRng, StdRng};
 use rand::{RngCore, SeedableRng};
+use std::any;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
-use std::iter;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
 
 /// Something that can extract information from an `InfoSource`.
 pub trait InfoSink {
     /// The output data.
     type Out;
-    /// Called by [InfoSource::draw](trait.InfoSource.html#tymethod.draw)
+    /// Called by [InfoSource::draw](trait.InfoSource.html#method.draw)
     fn sink<I: InfoSource>(&mut self, i: &mut I) -> Self::Out;
+    /// How recording sources label the data drawn by this sink. Defaults to
+    /// the name of the `Out` type, without module paths.
+    fn label(&self) -> Label {
+        type_label::<Self::Out>()
+    }
 }
 
 /// Something that an act as a source of test data.
@@ -17,10 +26,190 @@ pub trait InfoSource {
     /// Take a single byte from the source.
     fn draw_u8(&mut self) -> u8;
 
-    /// Call F with access to the data source.
-    fn draw<S: InfoSink>(&mut self, sink: S) -> S::Out
+    /// Call F with access to the data source. Recording sources label the
+    /// data drawn with the sink's [`label`](trait.InfoSink.html#method.label).
+    fn draw<S: InfoSink>(&mut self, mut sink: S) -> S::Out
+    where
+        Self: Sized,
+    {
+        self.enter_span();
+        let res = sink.sink(self);
+        self.exit_span(&|| sink.label());
+        res
+    }
+
+    /// As [`draw`](#method.draw), but labels the data drawn with `label`.
+    fn draw_labelled<L: Into<Label>, S: InfoSink>(&mut self, label: L, sink: S) -> S::Out
     where
-        Self: Sized;
+        Self: Sized,
+    {
+        self.draw(Labelled(label.into(), sink))
+    }
+
+    /// As [`draw`](#method.draw), for data that we only draw because of the
+    /// value drawn by the previous draw at the same level, eg: the contents
+    /// of an `Option` drawn after a flag that says whether it is present.
+    /// This lets the shrinker remove the two together. Goes via
+    /// [`draw`](#method.draw), so sources that wrap `draw` see these too.
+    fn draw_dependent<S: InfoSink>(&mut self, sink: S) -> S::Out
+    where
+        Self: Sized,
+    {
+        self.draw(Dependent(sink))
+    }
+
+    /// Marks the start of a nested draw. Called by [`draw`](#method.draw);
+    /// sources that record spans implement this, and wrapping sources
+    /// should forward it.
+    fn enter_span(&mut self) {}
+
+    /// Marks the nested draw most recently entered as depending on the
+    /// previous one. Called by [`draw_dependent`](#method.draw_dependent).
+    fn mark_dependent(&mut self) {}
+
+    /// Marks the end of the nested draw most recently entered. Building a
+    /// label can be costly, so only sources that record spans should call
+    /// `label`.
+    fn exit_span(&mut self, label: &dyn Fn() -> Label) {
+        let _ = label;
+    }
+}
+
+/// Describes what drew the data in a span, eg: the name of the type drawn.
+pub type Label = Cow<'static, str>;
+
+// Labels are built on every recorded draw, so we build each distinct label
+// once per thread, and keep it for the life of the thread.
+thread_local! {
+    static TYPE_LABELS: RefCell<LabelMap<(usize, usize), &'static str>> =
+        RefCell::new(HashMap::default());
+    static COMPOSITE_LABELS: RefCell<LabelMap<u64, Vec<CompositeLabel>>> =
+        RefCell::new(HashMap::default());
+}
+
+type LabelMap<K, V> = HashMap<K, V, BuildHasherDefault<LabelHasher>>;
+
+/// The `open`, `close` and `parts` that a composite label was built from,
+/// and the label itself.
+type CompositeLabel = (&'static str, &'static str, Vec<Label>, &'static str);
+
+/// A fast, but not collision resistant, hasher for caching labels (after
+/// the `FxHasher` used by rustc).
+#[derive(Default)]
+struct LabelHasher(u64);
+
+impl Hasher for LabelHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u64(b as u64);
+        }
+    }
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64)
+    }
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Returns the name of the type `T`, without module paths, eg: `Option<u16>`
+/// rather than `core::option::Option<u16>`. These names come from
+/// [`std::any::type_name`](https://doc.rust-lang.org/std/any/fn.type_name.html),
+/// so may vary between compiler versions.
+pub fn type_label<T: ?Sized>() -> Label {
+    let name = any::type_name::<T>();
+    let key = (name.as_ptr() as usize, name.len());
+    let label = TYPE_LABELS.with(|labels| {
+        *labels
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| leak(strip_paths(name)))
+    });
+    Label::Borrowed(label)
+}
+
+fn strip_paths(name: &str) -> String {
+    let mut label = String::with_capacity(name.len());
+    let mut rest = name;
+    while !rest.is_empty() {
+        // Copy each path as its last segment, and anything else as is.
+        let is_path = |c: char| c.is_alphanumeric() || c == '_' || c == ':';
+        let split = rest.find(|c| !is_path(c)).unwrap_or(rest.len()).max(1);
+        let (word, tail) = rest.split_at(split);
+        label.push_str(word.rsplit("::").next().unwrap_or(word));
+        rest = tail;
+    }
+    label
+}
+
+/// Returns a label for a generator built from others, made by joining the
+/// labels of its `parts` with commas, between `open` and `close`. eg:
+/// `composite_label("Vec<", &[u8s().label()], ">")` is `Vec<u8>`.
+pub fn composite_label(open: &'static str, parts: &[Label], close: &'static str) -> Label {
+    // Static strings are usually labels that we built earlier, so we hash
+    // those by address, which is quicker than hashing their contents.
+    let mut hasher = LabelHasher::default();
+    let by_address =
+        |s: &str, hasher: &mut LabelHasher| (s.as_ptr() as usize, s.len()).hash(hasher);
+    by_address(open, &mut hasher);
+    by_address(close, &mut hasher);
+    for part in parts {
+        match *part {
+            Label::Borrowed(s) => by_address(s, &mut hasher),
+            Label::Owned(ref s) => s.hash(&mut hasher),
+        }
+    }
+    let key = hasher.finish();
+    let label = COMPOSITE_LABELS.with(|labels| {
+        let mut labels = labels.borrow_mut();
+        let entries = labels.entry(key).or_insert_with(Vec::new);
+        let found = entries
+            .iter()
+            .find(|e| e.0 == open && e.1 == close && e.2[..] == *parts)
+            .map(|e| e.3);
+        found.unwrap_or_else(|| {
+            let label = leak(format!("{}{}{}", open, parts.join(", "), close));
+            entries.push((open, close, parts.to_vec(), label));
+            label
+        })
+    });
+    Label::Borrowed(label)
+}
+
+fn leak(label: String) -> &'static str {
+    Box::leak(label.into_boxed_str())
+}
+
+/// Labels the span that `S` is drawn in with a fixed label. See
+/// [`InfoSource::draw_labelled`](trait.InfoSource.html#method.draw_labelled).
+struct Labelled<S>(Label, S);
+
+impl<S: InfoSink> InfoSink for Labelled<S> {
+    type Out = S::Out;
+    fn sink<I: InfoSource>(&mut self, src: &mut I) -> Self::Out {
+        self.1.sink(src)
+    }
+    fn label(&self) -> Label {
+        self.0.clone()
+    }
+}
+
+/// Marks the span that `S` is drawn in as dependent. See
+/// [`InfoSource::draw_dependent`](trait.InfoSource.html#method.draw_dependent).
+struct Dependent<S>(S);
+
+impl<S: InfoSink> InfoSink for Dependent<S> {
+    type Out = S::Out;
+    fn sink<I: InfoSource>(&mut self, src: &mut I) -> Self::Out {
+        src.mark_dependent();
+        self.0.sink(src)
+    }
+    fn label(&self) -> Label {
+        self.0.label()
+    }
 }
 
 /// Generates data from an underlying Rng instance.
@@ -29,11 +218,15 @@ pub struct RngSource<R> {
     rng: R,
 }
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Default)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, Clone, Default)]
 pub(in data) struct Span {
-    start: usize,
-    end: usize,
-    level: usize,
+    pub(in data) start: usize,
+    pub(in data) end: usize,
+    pub(in data) level: usize,
+    pub(in data) label: Option<Label>,
+    // Whether we only drew this span because of what we drew in the
+    // previous span at the same level.
+    pub(in data) dependent: bool,
 }
 
 /// An adapter that can record the data drawn from an underlying source.
@@ -42,20 +235,25 @@ pub struct InfoRecorder<I> {
     inner: I,
     pub(crate) data: Vec<u8>,
     spans: Vec<Span>,
-    level: usize,
+    // The start of each span entered, and whether it is dependent.
+    starts: Vec<(usize, bool)>,
 }
 
-pub(in data) struct InfoPoolIntervalsIter(iter::Rev<::std::vec::IntoIter<Span>>);
+#[cfg(test)]
+pub(in data) struct InfoPoolIntervalsIter(::std::iter::Rev<::std::vec::IntoIter<Span>>);
 
 impl<'a, I: InfoSource + ?Sized> InfoSource for &'a mut I {
     fn draw_u8(&mut self) -> u8 {
         (**self).draw_u8()
     }
-    fn draw<S: InfoSink>(&mut self, mut sink: S) -> S::Out
-    where
-        Self: Sized,
-    {
-        sink.sink(self)
+    fn enter_span(&mut self) {
+        (**self).enter_span()
+    }
+    fn mark_dependent(&mut self) {
+        (**self).mark_dependent()
+    }
+    fn exit_span(&mut self, label: &dyn Fn() -> Label) {
+        (**self).exit_span(label)
     }
 }
 
@@ -66,7 +264,7 @@ impl<I> InfoRecorder<I> {
             inner: inner,
             data: Vec::new(),
             spans: Vec::new(),
-            level: 0,
+            starts: Vec::new(),
         }
     }
 
@@ -91,21 +289,32 @@ impl<I: InfoSource> InfoSource for InfoRecorder<I> {
         byte
     }
 
-    fn draw<S: InfoSink>(&mut self, mut sink: S) -> S::Out
-    where
-        Self: Sized,
-    {
+    fn enter_span(&mut self) {
         let start = self.data.len();
-        let level = self.level;
-        self.level += 1;
         trace!("-> InfoRecorder::draw @{}", start);
-        let res = sink.sink(self);
+        self.starts.push((start, false));
+    }
+
+    fn mark_dependent(&mut self) {
+        if let Some(&mut (_, ref mut dependent)) = self.starts.last_mut() {
+            *dependent = true;
+        }
+    }
+
+    fn exit_span(&mut self, label: &dyn Fn() -> Label) {
+        let label = label();
+        let (start, dependent) = self.starts.pop().expect("exit_span without enter_span");
+        let level = self.starts.len();
         let end = self.data.len();
         trace!("<- InfoRecorder::draw @{}", end);
-        debug!("Span: {:?}", (start, end));
-        self.level = level;
-        self.spans.push(Span { start, end, level });
-        res
+        debug!("Span: {:?}", (start, end, &label));
+        self.spans.push(Span {
+            start,
+            end,
+            level,
+            label: Some(label),
+            dependent,
+        });
     }
 }
 
@@ -118,16 +327,17 @@ impl RngSource<StdRng> {
     }
 }
 
+impl<R: RngCore> RngSource<R> {
+    /// Creates a RngSource that draws from the given `rng`.
+    pub fn of(rng: R) -> Self {
+        RngSource { rng }
+    }
+}
+
 impl<R: RngCore> InfoSource for RngSource<R> {
     fn draw_u8(&mut self) -> u8 {
         self.rng.next_u32() as u8
     }
-    fn draw<S: InfoSink>(&mut self, mut sink: S) -> S::Out
-    where
-        Self: Sized,
-    {
-        sink.sink(self)
-    }
 }
 
 /// A pool of data that we can draw upon to generate other types of data.
@@ -191,14 +401,55 @@ impl InfoPool {
         }
     }
 
+    /// How many spans were recorded whilst generating from this pool, ie:
+    /// roughly how many values (including intermediate values) were drawn.
+    pub fn span_count(&self) -> usize {
+        self.spans.len()
+    }
+
     #[cfg(test)]
     fn spans(&self) -> &[Span] {
         &self.spans
     }
 
+    #[cfg(test)]
     pub(in data) fn spans_iter(&self) -> InfoPoolIntervalsIter {
         InfoPoolIntervalsIter(self.spans.clone().into_iter().rev())
     }
+
+    // Spans are recorded as each draw finishes, so the descendants of each
+    // span directly precede it, and have higher levels. The helpers below
+    // navigate the tree of spans on that basis.
+
+    /// The spans nested within the span at index `i`, in recorded order.
+    pub(in data) fn descendants(&self, i: usize) -> &[Span] {
+        let level = self.spans[i].level;
+        let n = self.spans[..i]
+            .iter()
+            .rev()
+            .take_while(|s| s.level > level)
+            .count();
+        &self.spans[i - n..i]
+    }
+
+    /// The indices of the spans drawn directly by the span at index
+    /// `parent`, or at the top level if `None`, in recorded order.
+    pub(in data) fn children(&self, parent: Option<usize>) -> Vec<usize> {
+        let (range, level) = match parent {
+            Some(i) => (i - self.descendants(i).len()..i, self.spans[i].level + 1),
+            None => (0..self.spans.len(), 0),
+        };
+        range.filter(|&j| self.spans[j].level == level).collect()
+    }
+
+    /// The span drawn by the same parent directly before the span at index
+    /// `i`, if any.
+    pub(in data) fn previous_sibling(&self, i: usize) -> Option<&Span> {
+        let level = self.spans[i].level;
+        self.spans[..i - self.descendants(i).len()]
+            .last()
+            .filter(|s| s.level == level)
+    }
 }
 
 impl<'a> InfoReplay<'a> {
@@ -218,14 +469,8 @@ impl<'a> InfoSource for InfoReplay<'a> {
     fn draw_u8(&mut self) -> u8 {
         self.next_byte()
     }
-
-    fn draw<S: InfoSink>(&mut self, mut sink: S) -> S::Out
-    where
-        Self: Sized,
-    {
-        sink.sink(self)
-    }
 }
+#[cfg(test)]
 impl Iterator for InfoPoolIntervalsIter {
     type Item = Span;
     fn next(&mut self) -> Option<Self::Item> {
@@ -265,12 +510,6 @@ mod tests {
     extern crate env_logger;
     use super::*;
     use std::collections::BTreeSet;
-    impl<R: RngCore> RngSource<R> {
-        pub(crate) fn of(rng: R) -> Self {
-            RngSource { rng }
-        }
-    }
-
     struct FnSink<F>(F);
     impl<F: FnMut(&mut dyn InfoSource) -> R, R> InfoSink for FnSink<F> {
         type Out = R;
@@ -408,7 +647,7 @@ mod tests {
 
         let p = p.into_pool();
         assert!(
-            p.spans().contains(&Span::of_pair((2, 6))),
+            p.spans().iter().any(|s| s.as_pair() == (2, 6)),
             "Pool spans: {:?}; contains (2, 6)",
             p.spans()
         );
@@ -467,8 +706,8 @@ mod tests {
         }));
 
         assert_eq!(
-            p.spans_iter().collect::<Vec<_>>(),
-            vec![Span::of_pair((0, 4))]
+            p.spans_iter().map(|s| s.as_pair()).collect::<Vec<_>>(),
+            vec![(0, 4)]
         )
     }
 
@@ -495,8 +734,8 @@ mod tests {
         }
 
         assert_eq!(
-            p.spans_iter().collect::<Vec<_>>(),
-            vec![Span::of_pair((2, 6))]
+            p.spans_iter().map(|s| s.as_pair()).collect::<Vec<_>>(),
+            vec![(2, 6)]
         )
     }
 
@@ -525,6 +764,132 @@ mod tests {
         assert_eq!(actual, vec![v0]);
     }
 
+    #[test]
+    fn info_recorder_should_label_spans() {
+        let mut p = InfoRecorder::new(RngSource::new());
+        p.draw(FnSink(|src: &mut dyn InfoSource| src.draw_u8()));
+        p.draw_labelled("byte", FnSink(|src: &mut dyn InfoSource| src.draw_u8()));
+        let labels = p.spans_iter().map(|s| s.label.unwrap()).collect::<Vec<_>>();
+        assert_eq!(labels, vec!["byte", "u8"]);
+        let debug = format!("{:?}", p.into_pool());
+        assert!(debug.contains("\"byte\""), "{}", debug);
+    }
+
+    #[test]
+    fn info_recorder_should_record_draws_via_trait_objects() {
+        let mut p = InfoRecorder::new(RngSource::new());
+        {
+            let mut src = &mut p as &mut dyn InfoSource;
+            (&mut src).draw(FnSink(|src: &mut dyn InfoSource| {
+                src.draw_u8();
+                src.draw_u8()
+            }));
+        }
+        assert_eq!(
+            p.spans_iter().map(|s| s.as_pair()).collect::<Vec<_>>(),
+            vec![(0, 2)]
+        );
+    }
+
+    #[test]
+    fn info_recorder_should_mark_dependent_spans() {
+        let mut p = InfoRecorder::new(RngSource::new());
+        p.draw(FnSink(|src: &mut dyn InfoSource| src.draw_u8()));
+        p.draw_dependent(FnSink(|src: &mut dyn InfoSource| src.draw_u8()));
+        let dependent = p
+            .into_pool()
+            .spans_iter()
+            .map(|s| (s.as_pair(), s.dependent))
+            .collect::<Vec<_>>();
+        assert_eq!(dependent, vec![((1, 2), true), ((0, 1), false)]);
+    }
+
+    #[test]
+    fn dependent_draws_should_go_via_wrapped_draw() {
+        // Only overrides `draw`, like a source that counts draws would.
+        struct Counter<I>(I, usize);
+        impl<I: InfoSource> InfoSource for Counter<I> {
+            fn draw_u8(&mut self) -> u8 {
+                self.0.draw_u8()
+            }
+            fn draw<S: InfoSink>(&mut self, sink: S) -> S::Out {
+                self.1 += 1;
+                self.0.draw(sink)
+            }
+        }
+
+        let mut p = Counter(InfoRecorder::new(RngSource::new()), 0);
+        p.draw(FnSink(|src: &mut dyn InfoSource| src.draw_u8()));
+        p.draw_dependent(FnSink(|src: &mut dyn InfoSource| src.draw_u8()));
+        assert_eq!(p.1, 2);
+        let dependent =
+            p.0.into_pool()
+                .spans_iter()
+                .map(|s| s.dependent)
+                .collect::<Vec<_>>();
+        assert_eq!(dependent, vec![true, false]);
+    }
+
+    #[test]
+    fn only_recording_sources_should_build_labels() {
+        struct Unlabelled;
+        impl InfoSink for Unlabelled {
+            type Out = u8;
+            fn sink<I: InfoSource>(&mut self, src: &mut I) -> u8 {
+                src.draw_u8()
+            }
+            fn label(&self) -> Label {
+                panic!("label built for an unrecorded draw")
+            }
+        }
+
+        RngSource::new().draw(Unlabelled);
+        InfoPool::of_vec(vec![1]).replay().draw(Unlabelled);
+    }
+
+    #[test]
+    fn composite_labels_should_be_built_once() {
+        let parts = [Label::Borrowed("u8"), Label::Owned("x".to_string())];
+        let a = composite_label("(", &parts, ")");
+        let b = composite_label("(", &parts, ")");
+        assert_eq!(a, "(u8, x)");
+        match (a, b) {
+            (Label::Borrowed(a), Label::Borrowed(b)) => assert_eq!(a.as_ptr(), b.as_ptr()),
+            other => panic!("expected static labels: {:?}", other),
+        }
+        assert_eq!(composite_label("Vec<", &parts[..1], ">"), "Vec<u8>");
+    }
+
+    #[test]
+    fn pool_should_navigate_nested_spans() {
+        let span = |start, end, level| Span {
+            start,
+            end,
+            level,
+            ..Span::default()
+        };
+        let p = InfoPool {
+            data: vec![0; 4],
+            spans: vec![
+                span(0, 1, 2),
+                span(1, 2, 2),
+                span(0, 2, 1),
+                span(2, 4, 1),
+                span(0, 4, 0),
+            ],
+        };
+
+        assert_eq!(p.descendants(2).len(), 2);
+        assert_eq!(p.descendants(4).len(), 4);
+        assert!(p.descendants(3).is_empty());
+        assert_eq!(p.children(Some(4)), vec![2, 3]);
+        assert_eq!(p.children(Some(2)), vec![0, 1]);
+        assert_eq!(p.children(None), vec![4]);
+        assert_eq!(p.previous_sibling(3).map(Span::as_pair), Some((0, 2)));
+        assert_eq!(p.previous_sibling(1).map(Span::as_pair), Some((0, 1)));
+        assert_eq!(p.previous_sibling(2), None);
+    }
+
     #[test]
     fn info_recorder_works_recursively() {
         struct MyWidget;
@@ -553,6 +918,8 @@ mod tests {
             start: 2,
             end: 4,
             level: 1,
+            label: Some(Label::Borrowed("u16")),
+            dependent: false,
         };
         assert!(
             spans.contains(&expected),
@@ -0,0 +1,113 @@
+//! Differential testing: checking that two implementations agree.
+
+use std::fmt;
+
+use failures::Panic;
+use generators::Generator;
+use properties::{Counterexample, Property};
+
+/// What one side of an equivalence check did.
+enum Outcome<T> {
+    Returned(T),
+    Panicked(Panic),
+}
+
+/// Describes how two implementations disagreed.
+struct Divergence<T> {
+    left: Outcome<T>,
+    right: Outcome<T>,
+}
+
+impl<G: Generator> Property<G>
+where
+    G::Item: fmt::Debug + Clone,
+{
+    /// Checks that `f` and `g` return equal results for every input, eg: an
+    /// optimised implementation and a reference one. Each side is called on
+    /// a clone of the input, and a panic on either side counts as a
+    /// divergence. The minimal failure reports what each side returned.
+    pub fn check_equivalent<T, F, H>(self, f: F, g: H)
+    where
+        T: PartialEq + fmt::Debug,
+        F: Fn(G::Item) -> T,
+        H: Fn(G::Item) -> T,
+    {
+        self.check_equivalent_by(f, g, |a, b| a == b)
+    }
+
+    /// Like [`check_fails`](#method.check_fails), for
+    /// [`check_equivalent`](#method.check_equivalent).
+    pub fn check_equivalent_fails<T, F, H>(self, f: F, g: H) -> Counterexample<G::Item>
+    where
+        T: PartialEq + fmt::Debug,
+        F: Fn(G::Item) -> T,
+        H: Fn(G::Item) -> T,
+    {
+        self.check_equivalent_by_fails(f, g, |a, b| a == b)
+    }
+
+    /// Like [`check_equivalent`](#method.check_equivalent), but uses `eq`
+    /// to compare the results, eg: to allow for rounding errors.
+    pub fn check_equivalent_by<T, F, H, E>(self, f: F, g: H, eq: E)
+    where
+        T: fmt::Debug,
+        F: Fn(G::Item) -> T,
+        H: Fn(G::Item) -> T,
+        E: Fn(&T, &T) -> bool,
+    {
+        self.passed(self.run_equivalent_by(f, g, eq))
+    }
+
+    /// Like [`check_fails`](#method.check_fails), for
+    /// [`check_equivalent_by`](#method.check_equivalent_by).
+    pub fn check_equivalent_by_fails<T, F, H, E>(self, f: F, g: H, eq: E) -> Counterexample<G::Item>
+    where
+        T: fmt::Debug,
+        F: Fn(G::Item) -> T,
+        H: Fn(G::Item) -> T,
+        E: Fn(&T, &T) -> bool,
+    {
+        self.failed(self.run_equivalent_by(f, g, eq))
+    }
+
+    fn run_equivalent_by<T, F, H, E>(&self, f: F, g: H, eq: E) -> Option<Counterexample<G::Item>>
+    where
+        T: fmt::Debug,
+        F: Fn(G::Item) -> T,
+        H: Fn(G::Item) -> T,
+        E: Fn(&T, &T) -> bool,
+    {
+        self.run(&|arg: G::Item| {
+            let left = Outcome::of(|| f(arg.clone()));
+            let right = Outcome::of(|| g(arg));
+            match (&left, &right) {
+                (Outcome::Returned(a), Outcome::Returned(b)) if eq(a, b) => Ok(()),
+                _ => Err(Divergence { left, right }),
+            }
+        })
+    }
+}
+
+impl<T> Outcome<T> {
+    fn of<F: FnOnce() -> T>(f: F) -> Self {
+        match Panic::catch(f) {
+            Ok(val) => Outcome::Returned(val),
+            Err(panic) => Outcome::Panicked(panic),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Outcome<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Outcome::Returned(ref val) => write!(fmt, "returned {:?}", val),
+            Outcome::Panicked(ref panic) => write!(fmt, "panicked with {:?}", panic),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Divergence<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "left {:?}, but right {:?}", self.left, self.right)
+    }
+}
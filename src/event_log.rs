@@ -0,0 +1,167 @@
+//! Writes a machine readable summary of each property run, as one JSON
+//! object per line. See
+//! [`CheckConfig::event_log`](struct.CheckConfig.html#method.event_log).
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// The environment variable that we use to find the event log, if one is
+/// not set via the `CheckConfig`.
+pub(crate) const EVENT_LOG_VAR: &str = "SUPPOSITIONS_EVENT_LOG";
+
+/// Just enough of a JSON encoder to write out flat-ish objects.
+#[derive(Debug)]
+pub(crate) struct JsonObject {
+    buf: String,
+}
+
+impl JsonObject {
+    pub(crate) fn new() -> Self {
+        JsonObject {
+            buf: String::from("{"),
+        }
+    }
+
+    fn key(&mut self, key: &str) {
+        if self.buf.len() > 1 {
+            self.buf.push(',');
+        }
+        push_json_str(&mut self.buf, key);
+        self.buf.push(':');
+    }
+
+    pub(crate) fn string(mut self, key: &str, val: &str) -> Self {
+        self.key(key);
+        push_json_str(&mut self.buf, val);
+        self
+    }
+
+    /// Adds a number (or boolean) using its `Display` representation.
+    pub(crate) fn number<N: fmt::Display>(mut self, key: &str, val: N) -> Self {
+        self.key(key);
+        self.buf.push_str(&val.to_string());
+        self
+    }
+
+    /// Adds a floating point number; JSON has no representation for
+    /// infinities or NaN, so we write those as `null`.
+    pub(crate) fn float(mut self, key: &str, val: f64) -> Self {
+        self.key(key);
+        if val.is_finite() {
+            self.buf.push_str(&val.to_string());
+        } else {
+            self.buf.push_str("null");
+        }
+        self
+    }
+
+    pub(crate) fn objects<I: IntoIterator<Item = JsonObject>>(
+        mut self,
+        key: &str,
+        vals: I,
+    ) -> Self {
+        self.key(key);
+        self.buf.push('[');
+        for (i, val) in vals.into_iter().enumerate() {
+            if i > 0 {
+                self.buf.push(',');
+            }
+            self.buf.push_str(&val.finish());
+        }
+        self.buf.push(']');
+        self
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.buf.push('}');
+        self.buf
+    }
+}
+
+fn push_json_str(buf: &mut String, val: &str) {
+    buf.push('"');
+    for c in val.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if (c as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", c as u32)),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+/// Formats bytes as lower case hex, eg: `[0x1, 0xab]` as `01ab`.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Appends `record` as a single line to the log at `path`. Failing to write
+/// the log shouldn't fail the test, so we just warn about it.
+pub(crate) fn append(path: &Path, record: JsonObject) {
+    let line = record.finish();
+    let res = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| writeln!(f, "{}", line));
+    if let Err(e) = res {
+        warn!("Could not write event log to {:?}: {}", path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_encode_empty_object() {
+        assert_eq!(JsonObject::new().finish(), "{}");
+    }
+
+    #[test]
+    fn should_separate_fields() {
+        let obj = JsonObject::new()
+            .string("name", "prop")
+            .number("tests", 100)
+            .number("passed", true);
+        assert_eq!(obj.finish(), r#"{"name":"prop","tests":100,"passed":true}"#);
+    }
+
+    #[test]
+    fn should_write_non_finite_floats_as_null() {
+        let obj = JsonObject::new()
+            .float("a", 1.5)
+            .float("b", f64::INFINITY)
+            .float("c", f64::NAN);
+        assert_eq!(obj.finish(), r#"{"a":1.5,"b":null,"c":null}"#);
+    }
+
+    #[test]
+    fn should_escape_strings() {
+        let obj = JsonObject::new().string("msg", "a \"quoted\"\\\n\u{1}");
+        assert_eq!(obj.finish(), r#"{"msg":"a \"quoted\"\\\n\u0001"}"#);
+    }
+
+    #[test]
+    fn should_encode_nested_objects() {
+        let obj = JsonObject::new().objects(
+            "failures",
+            vec![
+                JsonObject::new().number("a", 1),
+                JsonObject::new().number("b", 2),
+            ],
+        );
+        assert_eq!(obj.finish(), r#"{"failures":[{"a":1},{"b":2}]}"#);
+    }
+
+    #[test]
+    fn should_format_hex() {
+        assert_eq!(hex(&[0x01, 0xab, 0x00]), "01ab00");
+    }
+}
@@ -0,0 +1,212 @@
+//! Describes the ways in which a test case can fail, so that we can tell
+//! failures caused by different bugs apart.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::panic;
+use std::sync::Once;
+
+use capture;
+use properties::CheckResult;
+
+thread_local! {
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// Describes how a single test case failed. Failures with the same
+/// [`fingerprint`](#method.fingerprint) are assumed to have the same cause.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Failure {
+    message: String,
+    location: Option<String>,
+    panicked: bool,
+}
+
+/// A panic caught whilst running a test subject.
+#[derive(Clone)]
+pub(crate) struct Panic {
+    message: String,
+    location: Option<String>,
+}
+
+impl Failure {
+    pub(crate) fn of_result<R: CheckResult + fmt::Debug>(res: &Result<R, Panic>) -> Option<Self> {
+        match *res {
+            Ok(ref r) if r.is_failure() => Some(Failure {
+                message: format!("{:?}", r),
+                location: None,
+                panicked: false,
+            }),
+            Ok(_) => None,
+            Err(ref p) => Some(Failure {
+                message: p.message.clone(),
+                location: p.location.clone(),
+                panicked: true,
+            }),
+        }
+    }
+
+    /// Either the panic message, or the debug representation of the
+    /// failing result.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Where the subject panicked, if it did.
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
+
+    /// Whether the subject panicked, rather than returning a failing result.
+    pub fn panicked(&self) -> bool {
+        self.panicked
+    }
+
+    /// A summary of the failure that ignores the details of the particular
+    /// input, so that failures with the same cause compare equal. For a
+    /// panic, this is where the subject panicked, as messages often include
+    /// the input. Otherwise, it is the first line of the panic message (or
+    /// of the failing result's debug representation), with each run of
+    /// digits replaced by `#`.
+    pub fn fingerprint(&self) -> String {
+        match self.location {
+            Some(ref location) => format!("panicked at {}", location),
+            None if self.panicked => format!("panicked: {}", summarize(&self.message)),
+            None => format!("returned {}", summarize(&self.message)),
+        }
+    }
+
+    /// Where the failure came from: where the subject panicked, or that it
+    /// returned a failing result. Unlike the
+    /// [`fingerprint`](#method.fingerprint), this never depends on the
+    /// message, which may change along with the input whilst shrinking.
+    pub(crate) fn origin(&self) -> String {
+        match self.location {
+            Some(ref location) => format!("panicked at {}", location),
+            None if self.panicked => "panicked".to_string(),
+            None => "returned".to_string(),
+        }
+    }
+}
+
+/// Returns the first line of `message`, with each run of digits replaced
+/// by a single `#`.
+fn summarize(message: &str) -> String {
+    let mut summary = String::new();
+    for c in message.lines().next().unwrap_or("").chars() {
+        if !c.is_ascii_digit() {
+            summary.push(c);
+        } else if !summary.ends_with('#') {
+            summary.push('#');
+        }
+    }
+    summary
+}
+
+impl Panic {
+    /// Runs `f`, and catches any panic along with the location it was
+    /// raised at.
+    pub(crate) fn catch<R, F: FnOnce() -> R>(f: F) -> Result<R, Panic> {
+        install_panic_hook();
+        PANIC_LOCATION.with(|loc| loc.borrow_mut().take());
+        let res = panic::catch_unwind(panic::AssertUnwindSafe(f));
+        res.map_err(|err| {
+            let message = if let Some(s) = err.downcast_ref::<&str>() {
+                s.to_string()
+            } else if let Some(s) = err.downcast_ref::<String>() {
+                s.to_string()
+            } else {
+                format!("Unrecognised panic result: {:?}", err)
+            };
+            let location = PANIC_LOCATION.with(|loc| loc.borrow_mut().take());
+            Panic { message, location }
+        })
+    }
+}
+
+// We only show the message, so that reports look the same as they would
+// for a plain `Result<R, String>`.
+impl fmt::Debug for Panic {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.message.fmt(fmt)
+    }
+}
+
+/// The panic payload doesn't include where the panic happened, so we
+/// install a hook that notes it down before deferring to the existing hook.
+/// If we are capturing output for the current test case, then we capture
+/// the panic message, too.
+fn install_panic_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(|l| l.to_string());
+            let _ = PANIC_LOCATION.try_with(|loc| *loc.borrow_mut() = location);
+            if !capture::try_capture(&format!("{}\n", info)) {
+                prev(info)
+            }
+        }));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure_of(res: Result<Result<(), String>, Panic>) -> Failure {
+        Failure::of_result(&res).expect("failure")
+    }
+
+    fn panic_at(message: &str, location: &str) -> Result<Result<(), String>, Panic> {
+        Err(Panic {
+            message: message.to_string(),
+            location: Some(location.to_string()),
+        })
+    }
+
+    #[test]
+    fn fingerprint_should_ignore_panic_messages() {
+        assert_eq!(
+            failure_of(panic_at("too big: [1, 2]\nmore", "a.rs:1:2")).fingerprint(),
+            failure_of(panic_at("too big: [1, 2, 3]", "a.rs:1:2")).fingerprint()
+        );
+    }
+
+    #[test]
+    fn fingerprint_should_ignore_numbers_in_results() {
+        assert_eq!(
+            failure_of(Ok(Err("3 is odd".to_string()))).fingerprint(),
+            failure_of(Ok(Err("5 is odd".to_string()))).fingerprint()
+        );
+    }
+
+    #[test]
+    fn fingerprint_should_distinguish_results() {
+        assert_ne!(
+            failure_of(Ok(Err("odd".to_string()))).fingerprint(),
+            failure_of(Ok(Err("large".to_string()))).fingerprint()
+        );
+    }
+
+    #[test]
+    fn fingerprint_should_distinguish_locations() {
+        assert_ne!(
+            failure_of(panic_at("odd", "a.rs:1:2")).fingerprint(),
+            failure_of(panic_at("odd", "b.rs:1:2")).fingerprint()
+        );
+    }
+
+    #[test]
+    fn origin_should_ignore_messages() {
+        assert_eq!(
+            failure_of(Ok(Err("[1, 2] too long".to_string()))).origin(),
+            failure_of(Ok(Err("zero".to_string()))).origin()
+        );
+        assert_ne!(
+            failure_of(Ok(Err("odd".to_string()))).origin(),
+            failure_of(panic_at("odd", "a.rs:1:2")).origin()
+        );
+    }
+}
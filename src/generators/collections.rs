@@ -86,6 +86,9 @@ impl<G: Generator> Generator for VecGenerator<G> {
         trace!("<- VecGenerator::generate");
         Ok(result)
     }
+    fn label(&self) -> Label {
+        composite_label("Vec<", &[self.inner.label()], ">")
+    }
 }
 
 impl Generator for InfoPoolGenerator {
@@ -100,6 +103,9 @@ impl Generator for InfoPoolGenerator {
 
         Ok(InfoPool::of_vec(result))
     }
+    fn label(&self) -> Label {
+        Label::Borrowed("InfoPool")
+    }
 }
 impl<G, C> CollectionGenerator<C, G> {
     /// Specify the mean number of _generated_ items. For collections with
@@ -161,7 +167,7 @@ impl<T: Clone> Generator for ChoiceGenerator<T> {
 mod tests {
     use env_logger;
     use generators::collections::*;
-    use generators::core::tests::*;
+    use generators::testing::*;
     use std::collections::LinkedList;
 
     #[test]
@@ -1,6 +1,9 @@
 use data::*;
+use rand::rngs::{OsRng, StdRng};
+use rand::{RngCore, SeedableRng};
 
 use super::numbers::{u32s, uniform_f32s};
+use super::sampling::samples_from;
 
 /// A convenience alias for generators that use the pool.
 pub type Maybe<T> = Result<T, DataError>;
@@ -82,6 +85,13 @@ pub trait Generator {
     /// This consumes a stream of bytes given by `source`, and generates a
     /// value of type `Self::Item`.
     fn generate<I: InfoSource>(&self, src: &mut I) -> Maybe<Self::Item>;
+    /// How recording sources label the data that we draw, so that the
+    /// shrinker can tell which draws produce the same kind of value. The
+    /// built in generators use fixed names, eg: `Option<u16>`; by default,
+    /// we use the [name of the item type](../data/fn.type_label.html).
+    fn label(&self) -> Label {
+        type_label::<Self::Item>()
+    }
     /// A convenience function to generate a value directly from an `InfoPool`.
     fn generate_from(&self, src: &InfoPool) -> Maybe<Self::Item> {
         self.generate(&mut src.replay())
@@ -124,6 +134,26 @@ pub trait Generator {
     {
         FlatMapped(self, fun)
     }
+
+    /// Generates `n` values from random data, eg: to see what a generator
+    /// produces. Skipped values are left out, and we give up after skipping
+    /// too many times, so this may return fewer than `n` values. See also
+    /// [`summarize`](fn.summarize.html).
+    fn sample(&self, n: usize) -> Vec<Self::Item>
+    where
+        Self: Sized,
+    {
+        self.sample_seeded(n, OsRng.next_u64())
+    }
+
+    /// Like [`sample`](#method.sample), but draws random data from a
+    /// generator seeded with `seed`, so that the results are reproducible.
+    fn sample_seeded(&self, n: usize, seed: u64) -> Vec<Self::Item>
+    where
+        Self: Sized,
+    {
+        samples_from(self, n, StdRng::seed_from_u64(seed)).values
+    }
 }
 
 impl<'a, G: Generator> InfoSink for &'a G {
@@ -131,6 +161,9 @@ impl<'a, G: Generator> InfoSink for &'a G {
     fn sink<I: InfoSource>(&mut self, src: &mut I) -> Self::Out {
         self.generate(src)
     }
+    fn label(&self) -> Label {
+        (**self).label()
+    }
 }
 
 /// Like [`Generator`](trait.Generator.html), but allows use as a trait object.
@@ -140,6 +173,8 @@ pub trait GeneratorObject {
     /// This consumes a stream of bytes given by `source`, and generates a
     /// value of type `Self::Item`.
     fn generate_obj(&self, src: &mut dyn InfoSource) -> Maybe<Self::Item>;
+    /// See [`Generator::label`](trait.Generator.html#method.label).
+    fn label_obj(&self) -> Label;
 }
 
 /// An extension trait that allows use of methods that assume Self has a known
@@ -167,6 +202,9 @@ impl<G: Generator> GeneratorObject for G {
     fn generate_obj(&self, mut src: &mut dyn InfoSource) -> Maybe<Self::Item> {
         (*self).generate(&mut src)
     }
+    fn label_obj(&self) -> Label {
+        self.label()
+    }
 }
 
 impl<T> Generator for Box<dyn GeneratorObject<Item = T>> {
@@ -174,6 +212,9 @@ impl<T> Generator for Box<dyn GeneratorObject<Item = T>> {
     fn generate<I: InfoSource>(&self, src: &mut I) -> Maybe<Self::Item> {
         (**self).generate_obj(src as &mut dyn InfoSource)
     }
+    fn label(&self) -> Label {
+        (**self).label_obj()
+    }
 }
 
 /// Generates boolean value with a 50% chance of being true.
@@ -219,6 +260,9 @@ impl<'a, G: Generator> Generator for &'a G {
     fn generate<I: InfoSource>(&self, src: &mut I) -> Maybe<Self::Item> {
         (**self).generate(src)
     }
+    fn label(&self) -> Label {
+        (**self).label()
+    }
 }
 
 impl Generator for BoolGenerator {
@@ -229,6 +273,9 @@ impl Generator for BoolGenerator {
         trace!("<- BoolGenerator::generate");
         Ok(res)
     }
+    fn label(&self) -> Label {
+        Label::Borrowed("bool")
+    }
 }
 
 impl<B: Generator<Item = bool>, G: Generator> Generator for OptionalGenerator<B, G> {
@@ -236,8 +283,10 @@ impl<B: Generator<Item = bool>, G: Generator> Generator for OptionalGenerator<B,
     fn generate<I: InfoSource>(&self, src: &mut I) -> Maybe<Self::Item> {
         trace!("-> OptionalGenerator::generate");
         let &OptionalGenerator(ref bools, ref gen) = self;
+        // We only draw the value if the flag is set, so mark it as dependent
+        // on the flag, so that the shrinker removes the two together.
         let result = if src.draw(bools)? {
-            Some(src.draw(gen)?)
+            Some(src.draw_dependent(gen)?)
         } else {
             None
         };
@@ -245,6 +294,9 @@ impl<B: Generator<Item = bool>, G: Generator> Generator for OptionalGenerator<B,
         trace!("<- OptionalGenerator::generate");
         Ok(result)
     }
+    fn label(&self) -> Label {
+        composite_label("Option<", &[self.1.label()], ">")
+    }
 }
 
 impl<G: Generator, H: Generator> Generator for ResultGenerator<G, H> {
@@ -260,6 +312,9 @@ impl<G: Generator, H: Generator> Generator for ResultGenerator<G, H> {
 
         Ok(result)
     }
+    fn label(&self) -> Label {
+        composite_label("Result<", &[self.0.label(), self.1.label()], ">")
+    }
 }
 
 impl<G: Generator, F: Fn(&G::Item) -> bool> Generator for Filtered<G, F> {
@@ -273,6 +328,9 @@ impl<G: Generator, F: Fn(&G::Item) -> bool> Generator for Filtered<G, F> {
             Err(DataError::SkipItem)
         }
     }
+    fn label(&self) -> Label {
+        self.0.label()
+    }
 }
 
 impl<G: Generator, R, F: Fn(G::Item) -> Maybe<R>> Generator for FilterMapped<G, F> {
@@ -299,8 +357,8 @@ impl<G: Generator, H: Generator, F: Fn(G::Item) -> H> Generator for FlatMapped<G
     type Item = H::Item;
     fn generate<I: InfoSource>(&self, src: &mut I) -> Maybe<Self::Item> {
         let &FlatMapped(ref gen, ref f) = self;
-        let gen2 = gen.generate(src)?;
-        let out = f(gen2).generate(src)?;
+        let gen2 = src.draw(gen)?;
+        let out = src.draw_dependent(&f(gen2))?;
         Ok(out)
     }
 }
@@ -322,6 +380,9 @@ impl Generator for WeightedCoinGenerator {
         trace!("<- WeightedCoinGenerator::generate");
         Ok(res)
     }
+    fn label(&self) -> Label {
+        Label::Borrowed("bool")
+    }
 }
 
 /// Allows the user to use one of a set of alternative generators Often
@@ -448,20 +509,10 @@ pub mod tests {
     use data::InfoPool;
     use env_logger;
     use generators::numbers::*;
-    use rand::rngs::{OsRng, StdRng};
+    use generators::testing::*;
+    use rand::rngs::StdRng;
     use rand::{RngCore, SeedableRng};
     use std::collections::BTreeMap;
-    use std::fmt;
-    use std::iter;
-
-    const SHORT_VEC_SIZE: usize = 256;
-
-    fn gen_random_vec() -> Vec<u8> {
-        let mut osrng = OsRng;
-        (0..SHORT_VEC_SIZE)
-            .map(|_| osrng.next_u32() as u8)
-            .collect::<Vec<u8>>()
-    }
 
     /// Create an `InfoPool` with a `size` length vector of random bytes
     /// using the generator `rng`. (Mostly used for testing).
@@ -470,85 +521,6 @@ pub mod tests {
         InfoPool::of_vec((0..size).map(|_| rng.next_u32() as u8).collect::<Vec<u8>>())
     }
 
-    pub fn should_generate_same_output_given_same_input<G: Generator>(gen: G)
-    where
-        G::Item: fmt::Debug + PartialEq,
-    {
-        for (p0, p1, v0, v1) in iter::repeat(())
-            .map(|_| gen_random_vec())
-            .map(|v0| (InfoPool::of_vec(v0.clone()), InfoPool::of_vec(v0)))
-            .flat_map(|(p0, p1)| {
-                gen.generate(&mut p0.replay())
-                    .and_then(|v0| gen.generate(&mut p1.replay()).map(|v1| (p0, p1, v0, v1)))
-            })
-            .take(100)
-        {
-            assert!(v0 == v1, "({:?} == {:?}) -> ({:?} == {:?})", p0, p1, v0, v1);
-        }
-    }
-
-    pub fn usually_generates_different_output_for_different_inputs<G: Generator>(gen: G)
-    where
-        G::Item: PartialEq,
-    {
-        let nitems = 100;
-        let differing = iter::repeat(())
-            .map(|_| (gen_random_vec(), gen_random_vec()))
-            .filter(|&(ref v0, ref v1)| v0 != v1)
-            .map(|(v0, v1)| (InfoPool::of_vec(v0), InfoPool::of_vec(v1)))
-            .flat_map(|(p0, p1)| {
-                gen.generate(&mut p0.replay())
-                    .and_then(|v0| gen.generate(&mut p1.replay()).map(|v1| (v0, v1)))
-            })
-            .take(nitems)
-            .filter(|&(ref v0, ref v1)| v0 != v1)
-            .count();
-        assert!(differing > 0, "Differing items:{} > 0", differing);
-    }
-
-    // Mostly only useful for scalar quantities. For collections, we basically say:
-    // `while booleans() { elemnt() }`
-    // So because booleans are imprecisly generated (ie: a pool of [0xff] ~ [0x80]),
-    // the source and result can have a differing ordering.
-    pub fn should_partially_order_same_as_source<G: Generator>(gen: G)
-    where
-        G::Item: PartialOrd + fmt::Debug + Clone,
-    {
-        should_partially_order_same_as_source_by(gen, |v| v.clone())
-    }
-
-    pub fn should_partially_order_same_as_source_by<
-        G: Generator,
-        K: PartialOrd,
-        F: Fn(&G::Item) -> K,
-    >(
-        gen: G,
-        key: F,
-    ) where
-        G::Item: fmt::Debug + PartialEq,
-    {
-        let nitems = 100;
-        for (p0, p1, v0, v1) in iter::repeat(())
-            .map(|_| (gen_random_vec(), gen_random_vec()))
-            .filter(|&(ref v0, ref v1)| v0 < v1)
-            .map(|(v0, v1)| (InfoPool::of_vec(v0), InfoPool::of_vec(v1)))
-            .flat_map(|(p0, p1)| {
-                gen.generate(&mut p0.replay())
-                    .and_then(|v0| gen.generate(&mut p1.replay()).map(|v1| (p0, p1, v0, v1)))
-            })
-            .take(nitems)
-        {
-            assert!(
-                key(&v0) <= key(&v1),
-                "({:?} < {:?}) -> ({:?} <= {:?})",
-                p0,
-                p1,
-                v0,
-                v1
-            );
-        }
-    }
-
     #[test]
     fn consts_should_generate_same_values() {
         let v1 = gen_random_vec();
@@ -577,28 +549,6 @@ pub mod tests {
         assert_eq!(bools.generate(&mut InfoPool::of_vec(v1).replay()), Ok(true));
     }
 
-    pub fn should_minimize_to<G: Generator>(gen: G, expected: G::Item)
-    where
-        G::Item: fmt::Debug + PartialEq,
-    {
-        let mut p;
-        loop {
-            p = InfoRecorder::new(RngSource::new());
-            match gen.generate(&mut p) {
-                Ok(_) => break,
-                Err(DataError::SkipItem) => (),
-                Err(DataError::PoolExhausted) => panic!("Not enough pool to generate data"),
-            }
-        }
-        let p = p.into_pool();
-        trace!("Before: {:?}", p);
-        let p = find_minimal(&gen, p, |_| true);
-        trace!("After: {:?}", p);
-
-        let val = gen.generate(&mut p.replay()).expect("generated value");
-        assert_eq!(val, expected);
-    }
-
     #[test]
     fn bools_should_generate_same_output_given_same_input() {
         should_generate_same_output_given_same_input(booleans())
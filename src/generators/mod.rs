@@ -5,10 +5,13 @@ mod collections;
 mod composition;
 mod core;
 mod numbers;
+mod sampling;
+pub mod testing;
 mod tuples;
 
 pub use self::collections::*;
 pub use self::composition::*;
 pub use self::core::*;
 pub use self::numbers::*;
+pub use self::sampling::*;
 pub use self::tuples::*;
@@ -60,6 +60,9 @@ macro_rules! unsigned_integer_gen {
                 }
                 Ok(val)
             }
+            fn label(&self) -> Label {
+                Label::Borrowed(stringify!($ty))
+            }
         }
     };
 }
@@ -93,6 +96,9 @@ where
 
         Ok(v.scale(limit.clone()))
     }
+    fn label(&self) -> Label {
+        self.0.label()
+    }
 }
 
 macro_rules! scale_int_impl {
@@ -186,6 +192,9 @@ macro_rules! signed_integer_gen {
                     Ok(uval as $ty)
                 }
             }
+            fn label(&self) -> Label {
+                Label::Borrowed(stringify!($ty))
+            }
         }
     };
 }
@@ -219,6 +228,9 @@ macro_rules! float_gen {
                     Ok(fval as $ty)
                 }
             }
+            fn label(&self) -> Label {
+                Label::Borrowed(stringify!($ty))
+            }
         }
     };
 }
@@ -242,6 +254,9 @@ macro_rules! uniform_float_gen {
                 let uval = inner_g.generate(src)?;
                 return Ok(uval as $ty / $inty::max_value() as $ty);
             }
+            fn label(&self) -> Label {
+                Label::Borrowed(stringify!($ty))
+            }
         }
     };
 }
@@ -251,8 +266,8 @@ uniform_float_gen!(uniform_f64s, u64s(), u64, f64);
 
 #[cfg(test)]
 mod tests {
-    use generators::core::tests::*;
     use generators::numbers::*;
+    use generators::testing::*;
 
     #[test]
     fn u8s_should_generate_same_output_given_same_input() {
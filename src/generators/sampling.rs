@@ -0,0 +1,287 @@
+//! Helpers for looking at what a generator produces, eg: when tuning
+//! weights or mean lengths, and how much it costs to produce it.
+
+use rand::rngs::{OsRng, StdRng};
+use rand::{RngCore, SeedableRng};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use data::*;
+use generators::core::*;
+
+/// How many times we'll skip per requested sample before giving up.
+const SKIPS_PER_SAMPLE: usize = 10;
+/// The width of the longest bar in a histogram.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Describes a batch of samples from a generator. See
+/// [`summarize`](fn.summarize.html). The `Display` implementation prints
+/// a human readable summary, including a histogram of sizes.
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    samples: usize,
+    skipped: usize,
+    distinct: usize,
+    pool_bytes: usize,
+    sizes: BTreeMap<usize, usize>,
+}
+
+/// Measures how expensive a generator is. See
+/// [`benchmark`](fn.benchmark.html). The `Display` implementation prints
+/// a one line summary.
+#[derive(Debug, Clone)]
+pub struct Throughput {
+    values: usize,
+    skipped: usize,
+    bytes: usize,
+    spans: usize,
+    elapsed: Duration,
+}
+
+/// The values generated from a series of pools, along with the total
+/// number of bytes and spans drawn (including for skipped values).
+pub(crate) struct Samples<T> {
+    pub(crate) values: Vec<T>,
+    pub(crate) skipped: usize,
+    pub(crate) bytes: usize,
+    pub(crate) spans: usize,
+}
+
+/// Generates up to `n` values from `gen`, drawing random data from `rng`.
+/// Skipped values are not included, and we give up after skipping too
+/// many times, so we may return fewer than `n` values.
+pub(crate) fn samples_from<G: Generator, R: RngCore>(
+    gen: &G,
+    n: usize,
+    rng: R,
+) -> Samples<G::Item> {
+    let mut src = RngSource::of(rng);
+    let mut samples = Samples {
+        values: Vec::with_capacity(n),
+        skipped: 0,
+        bytes: 0,
+        spans: 0,
+    };
+    while samples.values.len() < n && samples.skipped < n * SKIPS_PER_SAMPLE {
+        let mut rec = InfoRecorder::new(&mut src);
+        let res = rec.draw(gen);
+        let pool = rec.into_pool();
+        samples.bytes += pool.buffer().len();
+        samples.spans += pool.span_count();
+        match res {
+            Ok(val) => samples.values.push(val),
+            Err(DataError::SkipItem) => samples.skipped += 1,
+            Err(DataError::PoolExhausted) => unreachable!("random source exhausted"),
+        }
+    }
+    samples
+}
+
+/// Samples `n` values from `gen`, and summarizes them. Values are measured
+/// with `size` (eg: `Vec::len`) for the histogram, and considered distinct
+/// when their debug representations differ. For example:
+///
+/// ```rust
+/// use suppositions::generators::*;
+///
+/// let gen = vecs(u8s()).mean_length(4);
+/// println!("{}", summarize(&gen, 1000, |v| v.len()));
+/// ```
+pub fn summarize<G: Generator, F: Fn(&G::Item) -> usize>(gen: &G, n: usize, size: F) -> Summary
+where
+    G::Item: fmt::Debug,
+{
+    let samples = samples_from(gen, n, StdRng::seed_from_u64(OsRng.next_u64()));
+    let mut summary = Summary {
+        samples: samples.values.len(),
+        skipped: samples.skipped,
+        pool_bytes: samples.bytes,
+        ..Summary::default()
+    };
+    let mut distinct = BTreeSet::new();
+    for val in samples.values {
+        *summary.sizes.entry(size(&val)).or_insert(0) += 1;
+        distinct.insert(format!("{:?}", val));
+    }
+    summary.distinct = distinct.len();
+    summary
+}
+
+impl Summary {
+    /// How many values we generated.
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+    /// How many times the generator asked us to skip.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+    /// The proportion of attempts that were skipped.
+    pub fn skip_rate(&self) -> f64 {
+        let attempts = self.samples + self.skipped;
+        if attempts == 0 {
+            0.0
+        } else {
+            self.skipped as f64 / attempts as f64
+        }
+    }
+    /// How many distinct values we generated.
+    pub fn distinct(&self) -> usize {
+        self.distinct
+    }
+    /// The mean number of pool bytes used to generate each value, including
+    /// those drawn for skipped values.
+    pub fn mean_pool_bytes(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.pool_bytes as f64 / self.samples as f64
+        }
+    }
+    /// How many values we generated of each size.
+    pub fn sizes(&self) -> &BTreeMap<usize, usize> {
+        &self.sizes
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            fmt,
+            "Samples: {}; skipped: {} ({:.1}%)",
+            self.samples,
+            self.skipped,
+            self.skip_rate() * 100.0
+        )?;
+        writeln!(fmt, "Distinct values: {}", self.distinct)?;
+        writeln!(fmt, "Mean pool bytes: {:.1}", self.mean_pool_bytes())?;
+        writeln!(fmt, "Sizes:")?;
+        let max = self.sizes.values().cloned().max().unwrap_or(0);
+        for (size, &count) in self.sizes.iter() {
+            let width = (count * HISTOGRAM_WIDTH).div_ceil(max);
+            writeln!(fmt, "{:>6}: {:>6} {}", size, count, "#".repeat(width))?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates `n` values from `gen`, and measures how many bytes and spans
+/// are drawn for each, and how quickly. Useful for spotting generators
+/// that draw more data than they need to, eg:
+///
+/// ```rust
+/// use suppositions::generators::*;
+///
+/// println!("vecs(u8s()): {}", benchmark(&vecs(u8s()), 1000));
+/// ```
+///
+/// See also the `throughput` example.
+pub fn benchmark<G: Generator>(gen: &G, n: usize) -> Throughput {
+    let rng = StdRng::seed_from_u64(OsRng.next_u64());
+    let started = Instant::now();
+    let samples = samples_from(gen, n, rng);
+    let elapsed = started.elapsed();
+    Throughput {
+        values: samples.values.len(),
+        skipped: samples.skipped,
+        bytes: samples.bytes,
+        spans: samples.spans,
+        elapsed,
+    }
+}
+
+impl Throughput {
+    /// How many values we generated.
+    pub fn values(&self) -> usize {
+        self.values
+    }
+    /// How many times the generator asked us to skip.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+    /// The mean number of bytes drawn per value generated, including those
+    /// drawn for skipped values.
+    pub fn bytes_per_value(&self) -> f64 {
+        per_value(self.bytes, self.values)
+    }
+    /// The mean number of spans recorded per value generated.
+    pub fn spans_per_value(&self) -> f64 {
+        per_value(self.spans, self.values)
+    }
+    /// How many values we generated per second.
+    pub fn values_per_sec(&self) -> f64 {
+        self.values as f64 / self.elapsed.as_secs_f64().max(f64::MIN_POSITIVE)
+    }
+    /// How long generation took in total.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+fn per_value(total: usize, values: usize) -> f64 {
+    if values == 0 {
+        0.0
+    } else {
+        total as f64 / values as f64
+    }
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{} values ({} skipped); {:.1} bytes/value; {:.1} spans/value; {:.0} values/sec",
+            self.values,
+            self.skipped,
+            self.bytes_per_value(),
+            self.spans_per_value(),
+            self.values_per_sec()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use generators::collections::*;
+    use generators::numbers::*;
+
+    #[test]
+    fn should_sample_requested_number_of_values() {
+        assert_eq!(u8s().sample(10).len(), 10);
+    }
+
+    #[test]
+    fn seeded_samples_should_be_reproducible() {
+        let gen = vecs(u8s());
+        assert_eq!(gen.sample_seeded(20, 42), gen.sample_seeded(20, 42));
+        assert_ne!(gen.sample_seeded(20, 42), gen.sample_seeded(20, 43));
+    }
+
+    #[test]
+    fn should_give_up_when_always_skipping() {
+        let samples = samples_from(&u8s().filter(|_| false), 5, StdRng::seed_from_u64(0));
+        assert_eq!(samples.values.len(), 0);
+        assert_eq!(samples.skipped, 5 * SKIPS_PER_SAMPLE);
+    }
+
+    #[test]
+    fn benchmark_should_count_bytes_and_spans() {
+        let throughput = benchmark(&u32s(), 100);
+        assert_eq!(throughput.values(), 100);
+        assert_eq!(throughput.bytes_per_value(), 4.0);
+        assert!(throughput.spans_per_value() >= 1.0, "{}", throughput);
+        assert!(throughput.values_per_sec() > 0.0, "{}", throughput);
+    }
+
+    #[test]
+    fn summary_should_count_skips_and_sizes() {
+        let summary = summarize(&u8s().filter(|&n| n < 128), 100, |&n| (n / 64) as usize);
+        assert_eq!(summary.samples(), 100);
+        assert!(summary.skipped() > 0, "{}", summary);
+        assert_eq!(summary.sizes().values().sum::<usize>(), 100);
+        assert!(summary.sizes().keys().all(|&k| k < 2), "{}", summary);
+        assert!(summary.distinct() <= 128, "{}", summary);
+    }
+}
@@ -0,0 +1,140 @@
+//! Checks that a [`Generator`](../trait.Generator.html) is well behaved,
+//! for use in the tests for your own generators. For example:
+//!
+//! ```rust
+//! use suppositions::generators::*;
+//! use suppositions::generators::testing::*;
+//!
+//! let evens = u8s().map(|n| n & !1);
+//! should_generate_same_output_given_same_input(evens.clone());
+//! usually_generates_different_output_for_different_inputs(evens.clone());
+//! should_partially_order_same_as_source(evens.clone());
+//! should_minimize_to(evens, 0);
+//! ```
+//!
+//! Each check panics with a description of the problem if it fails, so can
+//! be called directly from a `#[test]` function.
+
+use data::*;
+use generators::core::*;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::fmt;
+use std::iter;
+
+const SHORT_VEC_SIZE: usize = 256;
+
+pub(crate) fn gen_random_vec() -> Vec<u8> {
+    let mut osrng = OsRng;
+    (0..SHORT_VEC_SIZE)
+        .map(|_| osrng.next_u32() as u8)
+        .collect::<Vec<u8>>()
+}
+
+/// Checks that `gen` is deterministic, ie: that it generates equal values
+/// when given pools with the same contents.
+pub fn should_generate_same_output_given_same_input<G: Generator>(gen: G)
+where
+    G::Item: fmt::Debug + PartialEq,
+{
+    for (p0, p1, v0, v1) in iter::repeat(())
+        .map(|_| gen_random_vec())
+        .map(|v0| (InfoPool::of_vec(v0.clone()), InfoPool::of_vec(v0)))
+        .flat_map(|(p0, p1)| {
+            gen.generate(&mut p0.replay())
+                .and_then(|v0| gen.generate(&mut p1.replay()).map(|v1| (p0, p1, v0, v1)))
+        })
+        .take(100)
+    {
+        assert!(v0 == v1, "({:?} == {:?}) -> ({:?} == {:?})", p0, p1, v0, v1);
+    }
+}
+
+/// Checks that `gen` actually depends on its input, ie: that at least some
+/// of the values generated from differing random pools differ.
+pub fn usually_generates_different_output_for_different_inputs<G: Generator>(gen: G)
+where
+    G::Item: PartialEq,
+{
+    let nitems = 100;
+    let differing = iter::repeat(())
+        .map(|_| (gen_random_vec(), gen_random_vec()))
+        .filter(|(v0, v1)| v0 != v1)
+        .map(|(v0, v1)| (InfoPool::of_vec(v0), InfoPool::of_vec(v1)))
+        .flat_map(|(p0, p1)| {
+            gen.generate(&mut p0.replay())
+                .and_then(|v0| gen.generate(&mut p1.replay()).map(|v1| (v0, v1)))
+        })
+        .take(nitems)
+        .filter(|(v0, v1)| v0 != v1)
+        .count();
+    assert!(differing > 0, "Differing items:{} > 0", differing);
+}
+
+/// Checks that `gen` is monotonic, ie: that a lexicographically smaller
+/// pool generates a value that is no greater. This is what allows
+/// shrinking the pool to shrink the value.
+// Mostly only useful for scalar quantities. For collections, we basically say:
+// `while booleans() { elemnt() }`
+// So because booleans are imprecisly generated (ie: a pool of [0xff] ~ [0x80]),
+// the source and result can have a differing ordering.
+pub fn should_partially_order_same_as_source<G: Generator>(gen: G)
+where
+    G::Item: PartialOrd + fmt::Debug + Clone,
+{
+    should_partially_order_same_as_source_by(gen, |v| v.clone())
+}
+
+/// Like [`should_partially_order_same_as_source`](fn.should_partially_order_same_as_source.html),
+/// but compares the values by the given `key`.
+pub fn should_partially_order_same_as_source_by<G: Generator, K: PartialOrd, F: Fn(&G::Item) -> K>(
+    gen: G,
+    key: F,
+) where
+    G::Item: fmt::Debug + PartialEq,
+{
+    let nitems = 100;
+    for (p0, p1, v0, v1) in iter::repeat(())
+        .map(|_| (gen_random_vec(), gen_random_vec()))
+        .filter(|(v0, v1)| v0 < v1)
+        .map(|(v0, v1)| (InfoPool::of_vec(v0), InfoPool::of_vec(v1)))
+        .flat_map(|(p0, p1)| {
+            gen.generate(&mut p0.replay())
+                .and_then(|v0| gen.generate(&mut p1.replay()).map(|v1| (p0, p1, v0, v1)))
+        })
+        .take(nitems)
+    {
+        assert!(
+            key(&v0) <= key(&v1),
+            "({:?} < {:?}) -> ({:?} <= {:?})",
+            p0,
+            p1,
+            v0,
+            v1
+        );
+    }
+}
+
+/// Checks that shrinking a randomly generated value results in `expected`,
+/// ie: the simplest value that `gen` can produce.
+pub fn should_minimize_to<G: Generator>(gen: G, expected: G::Item)
+where
+    G::Item: fmt::Debug + PartialEq,
+{
+    let mut p;
+    loop {
+        p = InfoRecorder::new(RngSource::new());
+        match gen.generate(&mut p) {
+            Ok(_) => break,
+            Err(DataError::SkipItem) => (),
+            Err(DataError::PoolExhausted) => panic!("Not enough pool to generate data"),
+        }
+    }
+    let p = p.into_pool();
+    trace!("Before: {:?}", p);
+    let p = find_minimal(&gen, p, |_| true);
+    trace!("After: {:?}", p);
+
+    let val = gen.generate(&mut p.replay()).expect("generated value");
+    assert_eq!(val, expected);
+}
@@ -10,10 +10,18 @@ macro_rules! tuple_generator_impl {
                     fn generate<In: InfoSource>(&self, src: &mut In) -> Maybe<Self::Item> {
                         // Gens
                         let &(ref $gen_a, $(ref $gen_n),*) = self;
-                        let $var_a = $gen_a.generate(src)?;
-                        $(let $var_n = $gen_n.generate(src)?;)*
+                        // Draw each component separately, so that the shrinker
+                        // can see where each one starts and ends.
+                        let $var_a = src.draw($gen_a)?;
+                        $(let $var_n = src.draw($gen_n)?;)*
                         Ok(($var_a, $($var_n),*))
                     }
+                    fn label(&self) -> Label {
+                        let &(ref $gen_a, $(ref $gen_n),*) = self;
+                        let labels: &[Label] = &[$gen_a.label(), $($gen_n.label()),*];
+                        let close = if labels.len() == 1 { ",)" } else { ")" };
+                        composite_label("(", labels, close)
+                    }
                 }
     );
 }
@@ -0,0 +1,322 @@
+//! Ready made batteries of properties that check a type's trait
+//! implementations obey the usual laws. For example:
+//!
+//! ```rust
+//! use suppositions::generators::*;
+//! use suppositions::laws;
+//!
+//! laws::of(vecs(u8s()))
+//!     .ord()
+//!     .eq_hash()
+//!     .clone_eq()
+//!     .monoid(Vec::new, |a, b| a.into_iter().chain(b).collect());
+//! ```
+//!
+//! When a law does not hold, we panic with the name of the law, along with
+//! the usual report of the minimal counterexample. To check that a law is
+//! broken, eg: when testing the laws themselves, use
+//! [`Laws::check_fails`](struct.Laws.html#method.check_fails).
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use data::{InfoPool, InfoSource, Label};
+use generators::{generator_fn, u8s, usizes, Generator, Maybe};
+use properties::{CheckConfig, CheckResult};
+
+/// Checks laws against values drawn from a generator. See the
+/// [module documentation](index.html).
+#[derive(Debug)]
+pub struct Laws<G> {
+    config: CheckConfig,
+    gen: G,
+    // Whether we note down the first broken law, rather than panicking.
+    recording: Cell<bool>,
+    broken: RefCell<Option<BrokenLaw>>,
+}
+
+/// A law that did not hold, as found by
+/// [`Laws::check_fails`](struct.Laws.html#method.check_fails).
+#[derive(Debug, Clone)]
+pub struct BrokenLaw {
+    name: String,
+    message: String,
+}
+
+impl BrokenLaw {
+    /// The name of the law, eg: `"Hash: equal values hash equally"`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The report of the minimal counterexample.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Checks laws against values from `gen`, with the default configuration.
+pub fn of<G: Generator>(gen: G) -> Laws<G> {
+    CheckConfig::default().laws(gen)
+}
+
+impl CheckConfig {
+    /// Checks laws against values from `gen`, using this configuration.
+    /// See the [laws module](laws/index.html).
+    pub fn laws<G: Generator>(&self, gen: G) -> Laws<G> {
+        Laws {
+            config: self.clone(),
+            gen,
+            recording: Cell::new(false),
+            broken: RefCell::new(None),
+        }
+    }
+}
+
+impl<G: Generator + Clone> Laws<G>
+where
+    G::Item: fmt::Debug + Clone,
+{
+    /// Checks that `Ord` is a total order, and agrees with `PartialOrd`
+    /// and `PartialEq`.
+    pub fn ord(&self) -> &Self
+    where
+        G::Item: Ord,
+    {
+        self.law("Ord: cmp is reflexive", self.gen.clone(), |a| {
+            a.cmp(&a) == Ordering::Equal
+        });
+        self.law("Ord: cmp is antisymmetric", self.pairs(), |(a, b)| {
+            a.cmp(&b) == b.cmp(&a).reverse()
+        });
+        self.law("Ord: cmp is transitive", self.triples(), |(a, b, c)| {
+            !(a <= b && b <= c) || a <= c
+        });
+        self.law(
+            "Ord: partial_cmp agrees with cmp",
+            self.pairs(),
+            |(a, b)| a.partial_cmp(&b) == Some(a.cmp(&b)),
+        );
+        self.law("Ord: eq agrees with cmp", self.pairs(), |(a, b)| {
+            (a == b) == (a.cmp(&b) == Ordering::Equal)
+        });
+        self
+    }
+
+    /// Checks that `Eq` is an equivalence relation, and that equal values
+    /// have equal hashes. So that values are often equal, we generate each
+    /// value after the first from the data that the one before it was
+    /// generated from, with a single byte changed.
+    pub fn eq_hash(&self) -> &Self
+    where
+        G::Item: Eq + Hash,
+    {
+        self.law("Eq: eq is reflexive", self.gen.clone(), |a| a.eq(&a));
+        self.law("Eq: eq is symmetric", self.similar_pairs(), |(a, b)| {
+            a.eq(&b) == b.eq(&a)
+        });
+        self.law(
+            "Eq: eq is transitive",
+            self.similar_triples(),
+            |(a, b, c)| !(a == b && b == c) || a == c,
+        );
+        self.law(
+            "Hash: equal values hash equally",
+            self.similar_pairs(),
+            |(a, b)| a != b || hash_of(&a) == hash_of(&b),
+        );
+        self
+    }
+
+    /// Checks that clones are equal to the original.
+    pub fn clone_eq(&self) -> &Self
+    where
+        G::Item: PartialEq,
+    {
+        self.law("Clone: clone equals original", self.gen.clone(), |a| {
+            a.clone() == a
+        });
+        self
+    }
+
+    /// Checks that `combine` is associative, and that `empty()` is its
+    /// identity element.
+    pub fn monoid<E, C>(&self, empty: E, combine: C) -> &Self
+    where
+        G::Item: PartialEq,
+        E: Fn() -> G::Item,
+        C: Fn(G::Item, G::Item) -> G::Item,
+    {
+        self.law(
+            "Monoid: combine is associative",
+            self.triples(),
+            |(a, b, c)| {
+                combine(combine(a.clone(), b.clone()), c.clone()) == combine(a, combine(b, c))
+            },
+        );
+        self.law("Monoid: empty is a left identity", self.gen.clone(), |a| {
+            combine(empty(), a.clone()) == a
+        });
+        self.law("Monoid: empty is a right identity", self.gen.clone(), |a| {
+            combine(a.clone(), empty()) == a
+        });
+        self
+    }
+
+    /// Checks that decoding an encoded value results in the original
+    /// value. A decoding error counts as a failure.
+    pub fn round_trip<T, X, F, H>(&self, encode: F, decode: H) -> &Self
+    where
+        G::Item: PartialEq,
+        X: fmt::Debug,
+        F: Fn(&G::Item) -> T,
+        H: Fn(T) -> Result<G::Item, X>,
+    {
+        self.law("Round trip: decode inverts encode", self.gen.clone(), |a| {
+            decode(encode(&a)).map(|b| b == a)
+        });
+        self
+    }
+
+    fn pairs(&self) -> (G, G) {
+        (self.gen.clone(), self.gen.clone())
+    }
+
+    fn triples(&self) -> (G, G, G) {
+        (self.gen.clone(), self.gen.clone(), self.gen.clone())
+    }
+
+    /// Like [`pairs`](#method.pairs), but each value is usually a slight
+    /// variation on the one before, so that values are often equal. Laws
+    /// about equal values can't fail for values that are never equal.
+    fn similar_pairs(&self) -> impl Generator<Item = (G::Item, G::Item)> {
+        let gen = self.gen.clone();
+        generator_fn(move |src| {
+            let mut src = src;
+            let (a, data) = recorded(&gen, &mut src)?;
+            let (b, _) = varied(&gen, &mut src, data)?;
+            Ok((a, b))
+        })
+    }
+
+    /// As [`similar_pairs`](#method.similar_pairs), for triples.
+    fn similar_triples(&self) -> impl Generator<Item = (G::Item, G::Item, G::Item)> {
+        let gen = self.gen.clone();
+        generator_fn(move |src| {
+            let mut src = src;
+            let (a, data) = recorded(&gen, &mut src)?;
+            let (b, data) = varied(&gen, &mut src, data)?;
+            let (c, _) = varied(&gen, &mut src, data)?;
+            Ok((a, b, c))
+        })
+    }
+
+    /// Checks that at least one of the laws checked by `laws` does not hold,
+    /// and returns the first one that doesn't, rather than panicking. Panics
+    /// if every law holds.
+    ///
+    /// ```
+    /// use suppositions::generators::*;
+    /// use suppositions::laws;
+    ///
+    /// let broken = laws::of(u8s()).check_fails(|l| {
+    ///     l.monoid(|| 0, |a, b| a.wrapping_sub(b));
+    /// });
+    /// assert_eq!(broken.name(), "Monoid: combine is associative");
+    /// ```
+    pub fn check_fails<F: FnOnce(&Self)>(&self, laws: F) -> BrokenLaw {
+        self.recording.set(true);
+        laws(self);
+        self.recording.set(false);
+        match self.broken.borrow_mut().take() {
+            Some(broken) => broken,
+            None => panic!("Expected a law to fail, but all held"),
+        }
+    }
+
+    /// Checks a single law, and includes its name in any failure.
+    fn law<H, R, F>(&self, name: &str, gen: H, subject: F)
+    where
+        H: Generator,
+        H::Item: fmt::Debug,
+        R: CheckResult + fmt::Debug,
+        F: Fn(H::Item) -> R,
+    {
+        if self.broken.borrow().is_some() {
+            return;
+        }
+        let counterexample = match self.config.property(gen).named(name).run(&subject) {
+            Some(counterexample) => counterexample,
+            None => return,
+        };
+        if !self.recording.get() {
+            panic!("Law {:?} failed: {}", name, counterexample.message())
+        }
+        *self.broken.borrow_mut() = Some(BrokenLaw {
+            name: name.to_string(),
+            message: counterexample.message().to_string(),
+        });
+    }
+}
+
+/// Passes everything on to the underlying source, and notes down the bytes
+/// drawn.
+struct Recording<I> {
+    inner: I,
+    data: Vec<u8>,
+}
+
+impl<I: InfoSource> InfoSource for Recording<I> {
+    fn draw_u8(&mut self) -> u8 {
+        let byte = self.inner.draw_u8();
+        self.data.push(byte);
+        byte
+    }
+    fn enter_span(&mut self) {
+        self.inner.enter_span()
+    }
+    fn mark_dependent(&mut self) {
+        self.inner.mark_dependent()
+    }
+    fn exit_span(&mut self, label: &dyn Fn() -> Label) {
+        self.inner.exit_span(label)
+    }
+}
+
+/// Draws a value from `gen`, and returns it along with the bytes that it was
+/// generated from.
+fn recorded<G: Generator, I: InfoSource>(gen: &G, src: &mut I) -> Maybe<(G::Item, Vec<u8>)> {
+    let mut recording = Recording {
+        inner: src,
+        data: Vec::new(),
+    };
+    let val = recording.draw(gen)?;
+    Ok((val, recording.data))
+}
+
+/// Generates a value from `data` with a single byte changed, and returns it
+/// along with the changed bytes. We draw which byte to change, and what to
+/// change it to, from `src`, so these shrink towards changing the first byte
+/// to zero.
+fn varied<G: Generator, I: InfoSource>(
+    gen: &G,
+    src: &mut I,
+    data: Vec<u8>,
+) -> Maybe<(G::Item, Vec<u8>)> {
+    let mut data = data;
+    if !data.is_empty() {
+        let pos = src.draw(&usizes())? % data.len();
+        data[pos] = src.draw(&u8s())?;
+    }
+    let val = gen.generate_from(&InfoPool::of_vec(data.clone()))?;
+    Ok((val, data))
+}
+
+fn hash_of<T: Hash>(val: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    val.hash(&mut hasher);
+    hasher.finish()
+}
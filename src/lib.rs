@@ -47,8 +47,16 @@ extern crate hex_slice;
 extern crate log;
 extern crate rand;
 
+pub mod capture;
 pub mod data;
+mod equivalence;
+mod event_log;
+mod failures;
 pub mod generators;
+pub mod laws;
 mod properties;
+mod targets;
 
+pub use failures::Failure;
 pub use properties::*;
+pub use targets::target;
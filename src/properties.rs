@@ -1,8 +1,19 @@
+use rand::rngs::{OsRng, StdRng};
+use rand::{RngCore, SeedableRng};
+use std::cell::RefCell;
+use std::cmp::max;
+use std::env;
 use std::fmt;
-use std::panic;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use capture;
 use data::*;
+use event_log::*;
+use failures::*;
 use generators::*;
+use targets::*;
 
 /// Configuration that allows the user to override how many tests, skipped-tests etc.
 /// are permitted.
@@ -10,6 +21,14 @@ use generators::*;
 pub struct CheckConfig {
     num_tests: usize,
     max_skips: usize,
+    target_ratio: f64,
+    keep_going: bool,
+    allow_slippage: bool,
+    replays: usize,
+    max_failures: usize,
+    seed: Option<u64>,
+    event_log: Option<PathBuf>,
+    capture_output: bool,
 }
 
 impl Default for CheckConfig {
@@ -18,6 +37,14 @@ impl Default for CheckConfig {
         CheckConfig {
             num_tests: num_tests,
             max_skips: num_tests * 10,
+            target_ratio: 0.5,
+            keep_going: false,
+            allow_slippage: false,
+            replays: 3,
+            max_failures: 10,
+            seed: None,
+            event_log: None,
+            capture_output: true,
         }
     }
 }
@@ -37,11 +64,105 @@ impl CheckConfig {
             ..self.clone()
         }
     }
+    /// Overrides the proportion of tests that are spent hill-climbing
+    /// towards higher [`target`](fn.target.html) scores, once any have been
+    /// reported. Should be between 0.0 and 1.0.
+    pub fn target_ratio(&self, target_ratio: f64) -> Self {
+        CheckConfig {
+            target_ratio,
+            ..self.clone()
+        }
+    }
+    /// When `true`, we carry on testing after finding a failure, and then
+    /// report a minimal example for each distinct kind of failure found, up
+    /// to [`max_failures`](#method.max_failures). Failures are told apart
+    /// by their
+    /// [`fingerprint`](struct.Failure.html#method.fingerprint), or by
+    /// [`Property::group_failures_by`](struct.Property.html#method.group_failures_by).
+    pub fn keep_going(&self, keep_going: bool) -> Self {
+        CheckConfig {
+            keep_going,
+            ..self.clone()
+        }
+    }
+    /// Overrides how many distinct failures we find (and so shrink) when
+    /// using [`keep_going`](#method.keep_going), before we stop testing.
+    pub fn max_failures(&self, max_failures: usize) -> Self {
+        CheckConfig {
+            max_failures: max_failures.max(1),
+            ..self.clone()
+        }
+    }
+    /// When shrinking, we normally only accept smaller examples that fail in
+    /// the same way as the original failure, so that we don't end up
+    /// reporting an unrelated bug. That is, they must panic at the same
+    /// [`location`](struct.Failure.html#method.location), or return a
+    /// failing result if the original did; or have the same key under
+    /// [`Property::group_failures_by`](struct.Property.html#method.group_failures_by),
+    /// if set. Setting this to `true` accepts any failing example.
+    pub fn allow_slippage(&self, allow_slippage: bool) -> Self {
+        CheckConfig {
+            allow_slippage,
+            ..self.clone()
+        }
+    }
+    /// Overrides how many times we re-run the minimal failing example
+    /// before reporting it (at least once). If it does not fail every
+    /// time, the failure is reported as flaky. Whilst shrinking, we
+    /// only accept examples that fail on every one of these runs.
+    pub fn replays(&self, replays: usize) -> Self {
+        CheckConfig {
+            replays: max(replays, 1),
+            ..self.clone()
+        }
+    }
+    /// Uses a fixed seed for the random data generation, rather than a
+    /// random one. The seed used is recorded in the event log (see
+    /// [`event_log`](#method.event_log)), so this can be used to reproduce a
+    /// run.
+    pub fn seed(&self, seed: u64) -> Self {
+        CheckConfig {
+            seed: Some(seed),
+            ..self.clone()
+        }
+    }
+    /// Appends a summary of each property run to the file at `path`, as one
+    /// JSON object per line. If this is not set, we use the path given by
+    /// the `SUPPOSITIONS_EVENT_LOG` environment variable, if any.
+    ///
+    /// Each object includes the `property` name (see
+    /// [`Property::named`](struct.Property.html#method.named)), the `seed`
+    /// (as a string, to avoid loss of precision), the `outcome`
+    /// (`"passed"` or `"failed"`), `tests_run`, `items_skipped`,
+    /// `duration_secs`, a list of `targets`, and a list of `failures`. Each
+    /// target has the `label` passed to [`target`](fn.target.html), and the
+    /// best `score` seen for it. Each failure has the `message` we would
+    /// panic with, the `minimal_pool` as hex, the number of `shrink_steps`
+    /// taken, and the `shrink_duration_secs`.
+    pub fn event_log<P: AsRef<Path>>(&self, path: P) -> Self {
+        CheckConfig {
+            event_log: Some(path.as_ref().to_path_buf()),
+            ..self.clone()
+        }
+    }
+    /// Whether to capture output written whilst each test case runs (see
+    /// the [capture module](capture/index.html)). When set, which is the
+    /// default, we discard the output of passing and shrinking attempts, and
+    /// include the output of the minimal failing example in the report.
+    pub fn capture_output(&self, capture_output: bool) -> Self {
+        CheckConfig {
+            capture_output,
+            ..self.clone()
+        }
+    }
     /// This is the main entry point for users of the library.
     pub fn property<G: Generator>(&self, gen: G) -> Property<G> {
         Property {
             config: self.clone(),
             gen: gen,
+            failure_key: None,
+            observer: RefCell::new(Box::new(())),
+            name: None,
         }
     }
 }
@@ -51,6 +172,40 @@ impl CheckConfig {
 pub struct Property<G> {
     config: CheckConfig,
     gen: G,
+    failure_key: Option<FailureKey>,
+    observer: RefCell<Box<dyn Observer>>,
+    name: Option<String>,
+}
+
+type FailureKey = Box<dyn Fn(&Failure) -> String>;
+
+/// The result of running the subject once, and its captured output.
+type Attempt<R> = (Result<R, Panic>, String);
+
+/// The minimal failing example found by
+/// [`Property::check_fails`](struct.Property.html#method.check_fails).
+#[derive(Debug, Clone)]
+pub struct Counterexample<T> {
+    value: T,
+    message: String,
+}
+
+impl<T> Counterexample<T> {
+    /// The minimal failing value.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Consumes the counterexample, returning the minimal failing value.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// The report that [`Property::check`](struct.Property.html#method.check)
+    /// would have panicked with.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
 /// This represents something that a check can return.
@@ -71,6 +226,106 @@ struct Stats {
     items_skipped: usize,
 }
 
+/// The first failure, and its pool, we found for each distinct kind of
+/// failure.
+#[derive(Debug, Default)]
+struct Failures {
+    found: Vec<(String, Failure, InfoPool)>,
+}
+
+/// Describes a failure, once shrunk.
+#[derive(Debug)]
+struct FailureReport {
+    message: String,
+    minimal: InfoPool,
+    shrink_steps: usize,
+    shrink_time: Duration,
+}
+
+/// Counts the shrinks accepted, before passing events onto `inner`.
+struct ShrinkCounter<O> {
+    inner: O,
+    accepted: usize,
+}
+
+impl<O: Observer> Observer for ShrinkCounter<O> {
+    fn observe(&mut self, event: &Event) {
+        if let Event::CandidateAccepted(_) = *event {
+            self.accepted += 1;
+        }
+        self.inner.observe(event)
+    }
+}
+
+impl<G> Property<G> {
+    /// Overrides how we decide whether two failures have the same cause,
+    /// when using [`CheckConfig::keep_going`](struct.CheckConfig.html#method.keep_going).
+    /// Failures that map to the same key are reported once.
+    pub fn group_failures_by<K: Fn(&Failure) -> String + 'static>(self, key: K) -> Self {
+        Property {
+            failure_key: Some(Box::new(key)),
+            ..self
+        }
+    }
+
+    /// Reports progress to `observer` as we generate test cases and
+    /// shrink any failures. See [`Event`](data/enum.Event.html) for what
+    /// gets reported.
+    pub fn with_observer<O: Observer + 'static>(self, observer: O) -> Self {
+        Property {
+            observer: RefCell::new(Box::new(observer)),
+            ..self
+        }
+    }
+
+    /// Names the property in the event log (see
+    /// [`CheckConfig::event_log`](struct.CheckConfig.html#method.event_log)).
+    /// Defaults to the name of the current thread, which for tests is the
+    /// name of the test.
+    pub fn named<S: Into<String>>(self, name: S) -> Self {
+        Property {
+            name: Some(name.into()),
+            ..self
+        }
+    }
+
+    /// Provides each test case with a fresh fixture (eg: a temporary
+    /// directory), created by `setup` and disposed of by `teardown`. This
+    /// includes every attempt whilst shrinking. See
+    /// [`FixtureProperty::check`](struct.FixtureProperty.html#method.check).
+    pub fn with_fixture<X, S: Fn() -> X, T: Fn(X)>(
+        self,
+        setup: S,
+        teardown: T,
+    ) -> FixtureProperty<G, S, T> {
+        FixtureProperty {
+            property: self,
+            setup,
+            teardown,
+        }
+    }
+
+    fn emit(&self, event: Event) {
+        self.observer.borrow_mut().observe(&event)
+    }
+
+    fn key_of(&self, failure: &Failure) -> String {
+        match self.failure_key {
+            Some(ref key) => key(failure),
+            None => failure.fingerprint(),
+        }
+    }
+
+    /// Unlike the default grouping, we never compare messages by default
+    /// when shrinking, as they often include the input.
+    fn slippage_key(&self, failure: &Failure) -> String {
+        match self.failure_key {
+            Some(ref key) => key(failure),
+            None => failure.origin(),
+        }
+    }
+}
+
 impl<G: Generator> Property<G>
 where
     G::Item: fmt::Debug,
@@ -78,35 +333,260 @@ where
     /// Use this function to sepecify the thing you wish to check. Because we include the
     /// debug representation of the input and the output within the
     pub fn check<R: CheckResult + fmt::Debug, F: Fn(G::Item) -> R>(self, subject: F) {
+        self.passed(self.run(&subject))
+    }
+
+    /// Checks that the property does not hold, ie: that we can find a
+    /// failing example within the configured number of tests. Returns the
+    /// minimal failing example, so that callers can make assertions about
+    /// it. Panics if no failure is found.
+    pub fn check_fails<R: CheckResult + fmt::Debug, F: Fn(G::Item) -> R>(
+        self,
+        subject: F,
+    ) -> Counterexample<G::Item> {
+        self.failed(self.run(&subject))
+    }
+
+    /// Panics with the report of the counterexample, if any.
+    pub(crate) fn passed(&self, res: Option<Counterexample<G::Item>>) {
+        if let Some(counterexample) = res {
+            panic!("{}", counterexample.message)
+        }
+    }
+
+    /// Returns the counterexample, or panics if there wasn't one.
+    pub(crate) fn failed(&self, res: Option<Counterexample<G::Item>>) -> Counterexample<G::Item> {
+        match res {
+            Some(counterexample) => counterexample,
+            None => panic!(
+                "Expected a failure, but none found in {} tests",
+                self.config.num_tests
+            ),
+        }
+    }
+
+    /// Runs the tests, and shrinks and reports any failures.
+    pub(crate) fn run<R: CheckResult + fmt::Debug, F: Fn(G::Item) -> R>(
+        &self,
+        subject: &F,
+    ) -> Option<Counterexample<G::Item>> {
+        let started = Instant::now();
+        let seed = self.config.seed.unwrap_or_else(|| OsRng.next_u64());
         let mut stats = Stats::default();
+        let mut targets = Targets::default();
+        let mut failures = Failures::default();
+        let mut rng = StdRng::seed_from_u64(seed);
         while stats.tests_run < self.config.num_tests {
             trace!(
                 "Tests run: {}; skipped:{}",
                 stats.tests_run,
                 stats.items_skipped
             );
-            self.try_one(&mut stats, &subject)
+            self.try_one(&mut stats, &mut targets, &mut failures, &mut rng, subject);
+            let enough = if self.config.keep_going {
+                self.config.max_failures
+            } else {
+                1
+            };
+            if failures.found.len() >= enough {
+                break;
+            }
+        }
+        let scores = targets.report();
+
+        let reports = failures
+            .found
+            .into_iter()
+            .map(|(_, failure, pool)| self.shrink_failure(subject, &failure, pool))
+            .collect::<Vec<_>>();
+        self.log_run(seed, &stats, &targets, started.elapsed(), &reports);
+        let first = match reports.first() {
+            Some(report) => report,
+            None => {
+                trace!("Completing okay");
+                return None;
+            }
+        };
+        let mut message = if reports.len() == 1 {
+            first.message.clone()
+        } else {
+            let mut msg = format!("Found {} distinct failures:", reports.len());
+            for (i, report) in reports.iter().enumerate() {
+                msg.push_str(&format!("\n{}: {}", i + 1, report.message));
+            }
+            msg
+        };
+        if let Some(scores) = scores {
+            message.push('\n');
+            message.push_str(&scores);
         }
-        trace!("Completing okay");
+        let value = first
+            .minimal
+            .replay()
+            .draw(&self.gen)
+            .expect("minimal example");
+        Some(Counterexample { value, message })
+    }
+
+    /// Like [`check`](#method.check), but allows the subject to hold mutable
+    /// state, such as caches or counters.
+    ///
+    /// Bear in mind that the subject is invoked once for each test case,
+    /// then again for every candidate we try whilst shrinking, and for each
+    /// replay of the minimal example. If state carried over from one
+    /// attempt changes the outcome of the next, the failure may be
+    /// reported as flaky, or shrink poorly. Use
+    /// [`check_mut_with_reset`](#method.check_mut_with_reset) to restore a
+    /// known state between attempts.
+    pub fn check_mut<R: CheckResult + fmt::Debug, F: FnMut(G::Item) -> R>(self, subject: F) {
+        let subject = RefCell::new(subject);
+        self.check(|arg| (*subject.borrow_mut())(arg))
+    }
+
+    /// Like [`check_fails`](#method.check_fails), for
+    /// [`check_mut`](#method.check_mut).
+    pub fn check_mut_fails<R: CheckResult + fmt::Debug, F: FnMut(G::Item) -> R>(
+        self,
+        subject: F,
+    ) -> Counterexample<G::Item> {
+        let subject = RefCell::new(subject);
+        self.check_fails(|arg| (*subject.borrow_mut())(arg))
+    }
+
+    /// Like [`check_mut`](#method.check_mut), but the subject's `state` is
+    /// passed explicitly, so that `reset` can restore it before every
+    /// attempt, including those made whilst shrinking.
+    pub fn check_mut_with_reset<S, R, Z, F>(self, state: S, reset: Z, subject: F)
+    where
+        R: CheckResult + fmt::Debug,
+        Z: FnMut(&mut S),
+        F: FnMut(&mut S, G::Item) -> R,
+    {
+        self.passed(self.run_mut_with_reset(state, reset, subject))
+    }
+
+    /// Like [`check_fails`](#method.check_fails), for
+    /// [`check_mut_with_reset`](#method.check_mut_with_reset).
+    pub fn check_mut_with_reset_fails<S, R, Z, F>(
+        self,
+        state: S,
+        reset: Z,
+        subject: F,
+    ) -> Counterexample<G::Item>
+    where
+        R: CheckResult + fmt::Debug,
+        Z: FnMut(&mut S),
+        F: FnMut(&mut S, G::Item) -> R,
+    {
+        self.failed(self.run_mut_with_reset(state, reset, subject))
+    }
+
+    fn run_mut_with_reset<S, R, Z, F>(
+        &self,
+        state: S,
+        reset: Z,
+        subject: F,
+    ) -> Option<Counterexample<G::Item>>
+    where
+        R: CheckResult + fmt::Debug,
+        Z: FnMut(&mut S),
+        F: FnMut(&mut S, G::Item) -> R,
+    {
+        let cell = RefCell::new((state, reset, subject));
+        self.run(&|arg| {
+            let (ref mut state, ref mut reset, ref mut subject) = *cell.borrow_mut();
+            reset(state);
+            subject(state, arg)
+        })
+    }
+
+    fn log_run(
+        &self,
+        seed: u64,
+        stats: &Stats,
+        targets: &Targets,
+        elapsed: Duration,
+        reports: &[FailureReport],
+    ) {
+        let path = match self
+            .config
+            .event_log
+            .clone()
+            .or_else(|| env::var_os(EVENT_LOG_VAR).map(PathBuf::from))
+        {
+            Some(path) => path,
+            None => return,
+        };
+        let name = self
+            .name
+            .clone()
+            .or_else(|| thread::current().name().map(String::from))
+            .unwrap_or_default();
+        let scores = targets.scores().map(|(label, score)| {
+            JsonObject::new()
+                .string("label", label)
+                .float("score", score)
+        });
+        let failures = reports.iter().map(|report| {
+            JsonObject::new()
+                .string("message", &report.message)
+                .string("minimal_pool", &hex(report.minimal.buffer()))
+                .number("shrink_steps", report.shrink_steps)
+                .float("shrink_duration_secs", report.shrink_time.as_secs_f64())
+        });
+        let record = JsonObject::new()
+            .string("property", &name)
+            .string("seed", &seed.to_string())
+            .string(
+                "outcome",
+                if reports.is_empty() {
+                    "passed"
+                } else {
+                    "failed"
+                },
+            )
+            .number("tests_run", stats.tests_run)
+            .number("items_skipped", stats.items_skipped)
+            .float("duration_secs", elapsed.as_secs_f64())
+            .objects("targets", scores)
+            .objects("failures", failures);
+        append(&path, record);
     }
 
     fn try_one<R: CheckResult + fmt::Debug, F: Fn(G::Item) -> R>(
         &self,
         stats: &mut Stats,
+        targets: &mut Targets,
+        failures: &mut Failures,
+        rng: &mut StdRng,
         subject: &F,
     ) {
-        let mut src = RngSource::new();
-        let mut pool = InfoRecorder::new(&mut src);
-        let result = pool.draw(&self.gen);
-        trace!("Pool: {:?}", pool);
-        let pool = pool.into_pool();
+        let (pool, result) = match self.hill_climb_from(stats, targets) {
+            Some(seed) => {
+                let candidate = ScalarMutator::new(seed.clone(), &mut *rng)
+                    .next()
+                    .expect("mutated pool");
+                let mut pool = InfoRecorder::new(candidate.replay());
+                let result = pool.draw(&self.gen);
+                (pool.into_pool(), result)
+            }
+            None => {
+                let mut src = RngSource::of(&mut *rng);
+                let mut pool = InfoRecorder::new(&mut src);
+                let result = pool.draw(&self.gen);
+                trace!("Pool: {:?}", pool);
+                (pool.into_pool(), result)
+            }
+        };
         match result {
             Ok(arg) => {
                 stats.tests_run += 1;
-                self.try_example(subject, pool, arg)
+                self.emit(Event::CaseGenerated(&pool));
+                self.try_example(subject, targets, failures, pool, arg)
             }
             Err(DataError::SkipItem) => {
                 stats.items_skipped += 1;
+                self.emit(Event::CaseSkipped(&pool));
                 trace!("Skip");
 
                 if stats.items_skipped >= self.config.max_skips {
@@ -122,55 +602,214 @@ where
         }
     }
 
+    /// Once we have used up our share of randomly generated tests, we
+    /// spend the rest of the run mutating the best scoring pools.
+    fn hill_climb_from<'a>(&self, stats: &Stats, targets: &'a mut Targets) -> Option<&'a InfoPool> {
+        let random_tests = self.config.num_tests as f64 * (1.0 - self.config.target_ratio);
+        if (stats.tests_run as f64) < random_tests {
+            return None;
+        }
+        targets.next_pool()
+    }
+
     fn try_example<R: CheckResult + fmt::Debug, F: Fn(G::Item) -> R>(
         &self,
         subject: &F,
+        targets: &mut Targets,
+        failures: &mut Failures,
         pool: InfoPool,
         arg: G::Item,
     ) {
-        let res = Self::attempt(&subject, arg);
+        let ((res, _), scores) = collect_scores(|| self.attempt(&subject, arg));
         trace!("Result: {:?} -> {:?}", pool.replay().draw(&self.gen), res);
-        if res.is_failure() {
-            let minpool = find_minimal(&self.gen, pool, |v| {
-                trace!("Shrink attempt: {:?}", v);
-                let res = Self::attempt(&subject, v);
-                trace!("Shrink attempt -> {:?}", res);
-                res.is_failure()
-            });
-            trace!("Minpool: {:?}", minpool);
-            trace!("Values: {:?}", minpool.replay().draw(&self.gen));
-            // TODO: Return an actual error from `find_minimal` and co, and
-            // use that in message.
-            let res = Self::attempt(
-                &subject,
-                minpool
-                    .replay()
-                    .draw(&self.gen)
-                    .expect("minimal example draw failure"),
-            );
-            panic!(
-                "Predicate failed for argument {:?}; check returned {:?}",
-                minpool.replay().draw(&self.gen),
-                res
-            )
+        targets.record(&pool, scores);
+        if let Some(failure) = Failure::of_result(&res) {
+            self.emit(Event::FailureFound(&pool));
+            let key = self.key_of(&failure);
+            debug!("Found failure: {:?}", key);
+            if !failures.found.iter().any(|(k, _, _)| k == &key) {
+                failures.found.push((key, failure, pool));
+            }
+        } else {
+            self.emit(Event::CasePassed(&pool));
         }
     }
 
-    fn attempt<R: CheckResult, F: Fn(G::Item) -> R>(subject: F, arg: G::Item) -> Result<R, String> {
-        let res = panic::catch_unwind(panic::AssertUnwindSafe(|| subject(arg)));
-        match res {
-            Ok(r) => Ok(r),
-            Err(err) => {
-                let msg = if let Some(s) = err.downcast_ref::<&str>() {
-                    s.to_string()
-                } else if let Some(s) = err.downcast_ref::<String>() {
-                    s.to_string()
-                } else {
-                    format!("Unrecognised panic result: {:?}", err)
-                };
-                Err(msg)
+    /// Shrinks the failing pool, and describes the minimal example. Unless
+    /// we allow slippage, we only accept shrinks that fail in the same way
+    /// as the original. We also only accept shrinks that fail on every
+    /// replay, so that we don't shrink towards an intermittent failure.
+    fn shrink_failure<R: CheckResult + fmt::Debug, F: Fn(G::Item) -> R>(
+        &self,
+        subject: &F,
+        original: &Failure,
+        pool: InfoPool,
+    ) -> FailureReport {
+        let started = Instant::now();
+        let key = self.slippage_key(original);
+        let replays = self.config.replays;
+        let fails_consistently = |t: &mut InfoRecorder<InfoReplay>| {
+            let arg = match t.draw(&self.gen) {
+                Ok(arg) => arg,
+                Err(_) => return false,
+            };
+            trace!("Shrink attempt: {:?}", arg);
+            let (res, _) = self.attempt(&subject, arg);
+            trace!("Shrink attempt -> {:?}", res);
+            if !self.fails_like(&key, &res) {
+                return false;
+            }
+            let candidate = InfoPool::of_vec(t.data.clone());
+            let (failed, _) = self.replay(subject, &key, &candidate, replays - 1);
+            failed == replays - 1
+        };
+        let mut counter = ShrinkCounter {
+            inner: &mut *self.observer.borrow_mut(),
+            accepted: 0,
+        };
+        let minpool = minimize_observed(&pool, &fails_consistently, &mut counter);
+        let shrink_time = started.elapsed();
+        trace!("Minpool: {:?}", minpool);
+        trace!("Values: {:?}", minpool.replay().draw(&self.gen));
+        let (failed, replayed) = self.replay(subject, &key, &minpool, replays);
+        let (res, output) = replayed.expect("minimal example replay");
+        // TODO: Return an actual error from `find_minimal` and co, and
+        // use that in message.
+        let mut msg = format!(
+            "Predicate failed for argument {:?}; check returned {:?}; failed on {}/{} replays",
+            minpool.replay().draw(&self.gen),
+            res,
+            failed,
+            replays
+        );
+        if !output.is_empty() {
+            msg.push_str("\nCaptured output:\n");
+            msg.push_str(&output);
+        }
+        let message = if failed < replays {
+            format!("Flaky failure: {}", msg)
+        } else {
+            msg
+        };
+        FailureReport {
+            message,
+            minimal: minpool,
+            shrink_steps: counter.accepted,
+            shrink_time,
+        }
+    }
+
+    fn fails_like<R: CheckResult + fmt::Debug>(&self, key: &str, res: &Result<R, Panic>) -> bool {
+        match Failure::of_result(res) {
+            Some(ref failure) if !self.config.allow_slippage => {
+                let matches = self.slippage_key(failure) == key;
+                if !matches {
+                    debug!("Rejecting shrink that slipped to: {:?}", failure);
+                }
+                matches
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Re-runs the subject on `pool` `times` times. Returns how many of
+    /// those runs failed like the original, along with the first such
+    /// failure (or the last result, if none did) and its captured output.
+    fn replay<R: CheckResult + fmt::Debug, F: Fn(G::Item) -> R>(
+        &self,
+        subject: &F,
+        key: &str,
+        pool: &InfoPool,
+        times: usize,
+    ) -> (usize, Option<Attempt<R>>) {
+        let mut failed = 0;
+        let mut reported = None;
+        for _ in 0..times {
+            let arg = match pool.replay().draw(&self.gen) {
+                Ok(arg) => arg,
+                Err(_) => continue,
+            };
+            let (res, output) = self.attempt(&subject, arg);
+            trace!("Replay -> {:?}", res);
+            if self.fails_like(key, &res) {
+                failed += 1;
+                if failed == 1 {
+                    reported = Some((res, output));
+                }
+            } else if failed == 0 {
+                reported = Some((res, output));
             }
         }
+        (failed, reported)
+    }
+
+    /// Runs the subject, along with any output captured whilst it ran.
+    fn attempt<R: CheckResult, F: Fn(G::Item) -> R>(&self, subject: F, arg: G::Item) -> Attempt<R> {
+        if self.config.capture_output {
+            capture::capturing(|| Panic::catch(|| subject(arg)))
+        } else {
+            (Panic::catch(|| subject(arg)), String::new())
+        }
+    }
+}
+
+/// A property whose test cases each get a fresh fixture. See
+/// [`Property::with_fixture`](struct.Property.html#method.with_fixture).
+pub struct FixtureProperty<G, S, T> {
+    property: Property<G>,
+    setup: S,
+    teardown: T,
+}
+
+/// Tears down the fixture when dropped, so that we tear down even if the
+/// subject panics.
+struct FixtureGuard<'a, X, T: Fn(X) + 'a> {
+    value: Option<X>,
+    teardown: &'a T,
+}
+
+impl<'a, X, T: Fn(X)> Drop for FixtureGuard<'a, X, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            (self.teardown)(value)
+        }
+    }
+}
+
+impl<G: Generator, X, S: Fn() -> X, T: Fn(X)> FixtureProperty<G, S, T>
+where
+    G::Item: fmt::Debug,
+{
+    /// Like [`Property::check`](struct.Property.html#method.check), but the
+    /// subject is also given a fresh fixture for each test case. The
+    /// fixture is torn down after each attempt, even when the subject
+    /// panics (although a panic in `teardown` whilst unwinding will abort
+    /// the process).
+    pub fn check<R: CheckResult + fmt::Debug, F: Fn(&mut X, G::Item) -> R>(self, subject: F) {
+        self.property.passed(self.run(subject))
+    }
+
+    /// Like [`Property::check_fails`](struct.Property.html#method.check_fails),
+    /// for [`check`](#method.check).
+    pub fn check_fails<R: CheckResult + fmt::Debug, F: Fn(&mut X, G::Item) -> R>(
+        self,
+        subject: F,
+    ) -> Counterexample<G::Item> {
+        self.property.failed(self.run(subject))
+    }
+
+    fn run<R: CheckResult + fmt::Debug, F: Fn(&mut X, G::Item) -> R>(
+        &self,
+        subject: F,
+    ) -> Option<Counterexample<G::Item>> {
+        self.property.run(&|arg| {
+            let mut fixture = FixtureGuard {
+                value: Some((self.setup)()),
+                teardown: &self.teardown,
+            };
+            subject(fixture.value.as_mut().expect("fixture"), arg)
+        })
     }
 }
 
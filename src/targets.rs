@@ -0,0 +1,122 @@
+//! Support for targeted property testing, where a property reports a score
+//! for each test case via [`target`](fn.target.html), and we spend some of
+//! our test budget hill-climbing towards the highest scoring inputs.
+
+use data::*;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    static SCORES: RefCell<Option<BTreeMap<String, f64>>> = const { RefCell::new(None) };
+}
+
+/// Reports a `score` for the current test case under the given `label`.
+/// Once we have seen a few test cases, the test runner will try to find
+/// inputs that maximise each label's score, which is useful for
+/// finding bugs that only occur on extreme inputs (eg: very deep queues).
+///
+/// If a label is reported more than once in a single test case, then we keep
+/// the highest score, and scores that are NaN are ignored. Calling this outside of a property check does nothing.
+/// The best score for each label is included in any failure report, and in
+/// the [event log](struct.CheckConfig.html#method.event_log).
+///
+/// ```
+/// use suppositions::*;
+/// use suppositions::generators::*;
+/// property(vecs(u8s())).check(|v| {
+///     target("length", v.len() as f64);
+///     v.len() < 1000
+/// });
+/// ```
+pub fn target(label: &str, score: f64) {
+    if score.is_nan() {
+        return;
+    }
+    SCORES.with(|scores| {
+        if let Some(ref mut scores) = *scores.borrow_mut() {
+            let best = scores.entry(label.to_string()).or_insert(score);
+            if score > *best {
+                *best = score;
+            }
+        }
+    })
+}
+
+/// Runs `f`, and returns any scores reported via `target` whilst it ran.
+pub(crate) fn collect_scores<T, F: FnOnce() -> T>(f: F) -> (T, BTreeMap<String, f64>) {
+    let prev = SCORES.with(|scores| scores.replace(Some(BTreeMap::new())));
+    let res = f();
+    let collected = SCORES.with(|scores| scores.replace(prev));
+    (res, collected.unwrap_or_default())
+}
+
+/// Keeps track of the best scoring pool for each label.
+#[derive(Debug, Default)]
+pub(crate) struct Targets {
+    best: BTreeMap<String, (f64, InfoPool)>,
+    next: usize,
+}
+
+impl Targets {
+    pub(crate) fn record(&mut self, pool: &InfoPool, scores: BTreeMap<String, f64>) {
+        for (label, score) in scores {
+            let improved = self
+                .best
+                .get(&label)
+                .map(|&(best, _)| score > best)
+                .unwrap_or(true);
+            if improved {
+                debug!("New best score for {:?}: {}", label, score);
+                self.best.insert(label, (score, pool.clone()));
+            }
+        }
+    }
+
+    /// Picks the best pool for each label in turn, so that every label gets
+    /// a share of the hill-climbing.
+    pub(crate) fn next_pool(&mut self) -> Option<&InfoPool> {
+        if self.best.is_empty() {
+            return None;
+        }
+        let idx = self.next % self.best.len();
+        self.next += 1;
+        self.best.values().nth(idx).map(|(_, pool)| pool)
+    }
+
+    /// The best score seen for each label.
+    pub(crate) fn scores(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.best
+            .iter()
+            .map(|(label, &(score, _))| (label.as_str(), score))
+    }
+
+    /// Logs the best score for each label, and returns them in a form
+    /// suitable for including in failure reports.
+    pub(crate) fn report(&self) -> Option<String> {
+        if self.best.is_empty() {
+            return None;
+        }
+        let mut msg = String::from("Best target scores:");
+        for (label, score) in self.scores() {
+            msg.push_str(&format!("\n  {}: {}", label, score));
+        }
+        info!("{}", msg);
+        Some(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_ignore_nan_scores() {
+        let ((), scores) = collect_scores(|| {
+            target("x", f64::NAN);
+            target("x", 1.0);
+            target("y", f64::NAN);
+        });
+        assert_eq!(scores.get("x"), Some(&1.0));
+        assert_eq!(scores.get("y"), None);
+    }
+}
@@ -0,0 +1,101 @@
+#[macro_use]
+extern crate log;
+extern crate suppositions;
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fmt;
+use std::panic;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use suppositions::capture::CaptureLogger;
+use suppositions::generators::*;
+use suppositions::*;
+
+static UNCAPTURED: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingLogger;
+
+impl Log for CountingLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+    fn log(&self, _: &Record) {
+        UNCAPTURED.fetch_add(1, Ordering::SeqCst);
+    }
+    fn flush(&self) {}
+}
+
+fn init_logging() {
+    CaptureLogger::new(Box::new(CountingLogger))
+        .install(LevelFilter::Info)
+        .unwrap_or_default();
+}
+
+fn failure_message<F: FnOnce() + panic::UnwindSafe>(f: F) -> String {
+    let err = panic::catch_unwind(f).expect_err("property should fail");
+    err.downcast_ref::<String>()
+        .cloned()
+        .expect("panic message")
+}
+
+#[test]
+fn only_the_minimal_failure_output_is_reported() {
+    let msg = failure_message(|| {
+        property(u8s()).check(|n| {
+            println_captured!("checking {}", n);
+            n < 10
+        })
+    });
+
+    assert!(msg.ends_with("Captured output:\nchecking 10\n"), "{}", msg);
+    assert_eq!(msg.matches("checking").count(), 1, "{}", msg);
+}
+
+#[test]
+fn log_records_are_captured_whilst_testing() {
+    init_logging();
+    let before = UNCAPTURED.load(Ordering::SeqCst);
+    let msg = failure_message(|| {
+        property(u8s()).check(|n| {
+            info!("checking {}", n);
+            n < 10
+        })
+    });
+
+    assert!(msg.contains("INFO capture: checking 10\n"), "{}", msg);
+    // Other tests may log concurrently, so we can only check that we
+    // haven't passed on every record.
+    assert!(UNCAPTURED.load(Ordering::SeqCst) - before < 100);
+}
+
+struct Boom;
+
+impl fmt::Display for Boom {
+    fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+        panic!("Boom")
+    }
+}
+
+#[test]
+fn panics_whilst_formatting_captured_output_are_reported() {
+    let failure = property(u8s()).check_fails(|_| {
+        print_captured!("{}", Boom);
+        true
+    });
+
+    assert!(format!("{:?}", failure).contains("Boom"), "{:?}", failure);
+}
+
+#[test]
+fn output_is_not_captured_when_disabled() {
+    let msg = failure_message(|| {
+        CheckConfig::default()
+            .capture_output(false)
+            .property(u8s())
+            .check(|n| {
+                print_captured!("");
+                n < 10
+            })
+    });
+
+    assert!(!msg.contains("Captured output"), "{}", msg);
+}
@@ -0,0 +1,35 @@
+extern crate suppositions;
+
+use suppositions::generators::*;
+use suppositions::*;
+
+#[test]
+fn check_mut_allows_stateful_subjects() {
+    let mut calls = 0;
+    property(u8s()).check_mut(|_| calls += 1);
+    assert_eq!(calls, 100);
+}
+
+#[test]
+fn check_mut_fails_returns_the_minimal_example() {
+    let mut largest = 0;
+    let cx = property(u8s()).check_mut_fails(|n| {
+        largest = largest.max(n);
+        n < 10
+    });
+    assert_eq!(cx.into_value(), 10);
+    assert!(largest >= 10);
+}
+
+#[test]
+fn check_mut_resets_state_between_attempts() {
+    let cx = property(u8s()).check_mut_with_reset_fails(
+        Vec::new(),
+        |seen| seen.clear(),
+        |seen, n| {
+            seen.push(n);
+            seen.len() == 1 && n < 10
+        },
+    );
+    assert_eq!(cx.into_value(), 10);
+}
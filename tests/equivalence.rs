@@ -0,0 +1,46 @@
+extern crate suppositions;
+
+use suppositions::generators::*;
+use suppositions::*;
+
+#[test]
+fn equivalent_implementations_pass() {
+    property(vecs(u8s())).check_equivalent(
+        |v| v.iter().map(|&n| n as u32).sum::<u32>(),
+        |v| v.into_iter().fold(0u32, |a, n| a + n as u32),
+    )
+}
+
+#[test]
+fn divergent_implementations_report_both_outputs() {
+    let cx = property(u8s()).check_equivalent_fails(|n| n, |n| if n < 10 { n } else { n - 1 });
+    assert_eq!(*cx.value(), 10);
+    assert!(
+        cx.message()
+            .contains("check returned Ok(Err(left returned 10, but right returned 9))"),
+        "{}",
+        cx.message()
+    );
+}
+
+#[test]
+fn panics_count_as_divergence() {
+    let cx = property(u8s())
+        .check_equivalent_fails(|n| 4u8.checked_div(n / 64).unwrap_or(0), |n| 4 / (n / 64));
+    assert_eq!(*cx.value(), 0);
+    assert!(
+        cx.message()
+            .contains("left returned 0, but right panicked with \"attempt to divide by zero\""),
+        "{}",
+        cx.message()
+    );
+}
+
+#[test]
+fn equivalence_can_use_custom_equality() {
+    property(u32s()).check_equivalent_by(
+        |n| n as f64 / 10.0,
+        |n| n as f64 * 0.1,
+        |a, b| (a - b).abs() <= a.abs() * 1e-9,
+    )
+}
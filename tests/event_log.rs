@@ -0,0 +1,63 @@
+extern crate suppositions;
+
+use std::cell::RefCell;
+use suppositions::generators::*;
+use suppositions::*;
+
+#[test]
+fn the_same_seed_generates_the_same_inputs() {
+    let run = |seed| {
+        let seen = RefCell::new(Vec::new());
+        CheckConfig::default()
+            .seed(seed)
+            .property(vecs(u8s()))
+            .check(|v| seen.borrow_mut().push(v));
+        seen.into_inner()
+    };
+
+    assert_eq!(run(42), run(42));
+    assert_ne!(run(42), run(43));
+}
+
+#[test]
+fn event_log_records_each_run() {
+    let path = ::std::env::temp_dir().join(format!(
+        "suppositions-event-log-{}.jsonl",
+        ::std::process::id()
+    ));
+    let _ = ::std::fs::remove_file(&path);
+    let config = CheckConfig::default().seed(7).event_log(&path);
+
+    config
+        .property(u8s())
+        .named("passing")
+        .check(|n| target("n", n as f64));
+    config
+        .property(u8s())
+        .named("failing")
+        .check_fails(|n| n < 10);
+
+    let log = ::std::fs::read_to_string(&path).expect("read event log");
+    let _ = ::std::fs::remove_file(&path);
+    let lines = log.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2, "{:?}", lines);
+    assert!(
+        lines[0]
+            .starts_with(r#"{"property":"passing","seed":"7","outcome":"passed","tests_run":100,"#),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[0].contains(r#""targets":[{"label":"n","score":"#),
+        "{}",
+        lines[0]
+    );
+    assert!(lines[0].ends_with(r#""failures":[]}"#), "{}", lines[0]);
+    assert!(
+        lines[1].starts_with(r#"{"property":"failing","seed":"7","outcome":"failed","#),
+        "{}",
+        lines[1]
+    );
+    assert!(lines[1].contains(r#""minimal_pool":"0a""#), "{}", lines[1]);
+    assert!(lines[1].contains(r#""shrink_steps":"#), "{}", lines[1]);
+}
@@ -0,0 +1,29 @@
+extern crate suppositions;
+
+use suppositions::generators::*;
+use suppositions::*;
+
+#[test]
+fn fixtures_are_torn_down_after_every_attempt() {
+    use std::cell::Cell;
+    let live = Cell::new(0usize);
+    let created = Cell::new(0usize);
+    let cx = property(u8s())
+        .with_fixture(
+            || {
+                live.set(live.get() + 1);
+                created.set(created.get() + 1);
+                Vec::new()
+            },
+            |_| live.set(live.get() - 1),
+        )
+        .check_fails(|fixture: &mut Vec<u8>, n| {
+            assert!(fixture.is_empty(), "fixture reused: {:?}", fixture);
+            fixture.push(n);
+            assert!(n < 10, "too big: {}", n);
+        });
+
+    assert_eq!(cx.into_value(), 10);
+    assert!(created.get() > 3, "created {}", created.get());
+    assert_eq!(live.get(), 0);
+}
@@ -461,3 +461,40 @@ fn should_allow_generation_from_function() {
         assert!(result.is_ok(), "Result: {:?}", result);
     });
 }
+
+#[test]
+fn generator_functions_can_use_the_generator_checks() {
+    use suppositions::generators::testing::*;
+    let gen = || {
+        generator_fn(|src: &mut dyn InfoSource| {
+            let hi = u8s().generate_obj(src)?;
+            let lo = u8s().generate_obj(src)?;
+            Ok((hi as u16) << 8 | lo as u16)
+        })
+    };
+
+    should_generate_same_output_given_same_input(gen());
+    usually_generates_different_output_for_different_inputs(gen());
+    should_partially_order_same_as_source(gen());
+    should_minimize_to(gen(), 0);
+}
+
+#[test]
+fn optional_draws_should_encode_as_documented() {
+    let pool = InfoPool::of_vec(vec![0x80, 0x0b, 0x0c]);
+    let mut rec = InfoRecorder::new(pool.replay());
+    assert_eq!(rec.draw(&optional(u16s())), Ok(Some(0x0b0c)));
+    assert_eq!(
+        rec.into_pool().to_text(),
+        "suppositions-pool 1\ndata 800b0c\nspan 0 1 1 bool\ndependent 1 3 1 u16\n\
+         span 0 3 0 Option<u16>\n"
+    );
+}
+
+#[test]
+fn generator_labels_should_not_include_module_paths() {
+    assert_eq!(vecs((u8s(), booleans())).label(), "Vec<(u8, bool)>");
+    assert_eq!(result(u8s(), u16s()).label(), "Result<u8, u16>");
+    assert_eq!(u8s().map(Some).label(), "Option<u8>");
+    assert_eq!(u8s().map(|n| vec![n]).boxed().label(), "Vec<u8>");
+}
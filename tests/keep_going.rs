@@ -0,0 +1,89 @@
+extern crate env_logger;
+extern crate suppositions;
+
+use suppositions::generators::*;
+use suppositions::*;
+
+fn odd_or_large(n: u8) {
+    assert!(n & 1 == 0, "odd: {}", n);
+    assert!(n < 128, "large: {}", n);
+}
+
+#[test]
+fn keep_going_reports_each_distinct_failure() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = CheckConfig::default()
+        .keep_going(true)
+        .property(u8s())
+        .check_fails(odd_or_large);
+    assert!(
+        cx.message().starts_with("Found 2 distinct failures"),
+        "{:?}",
+        cx
+    );
+}
+
+#[test]
+fn keep_going_reports_each_distinct_failing_result() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = CheckConfig::default()
+        .keep_going(true)
+        .property(u8s())
+        .check_fails(|n| match n {
+            n if n & 1 != 0 => Err("odd"),
+            n if n >= 128 => Err("large"),
+            _ => Ok(()),
+        });
+    assert!(
+        cx.message().starts_with("Found 2 distinct failures"),
+        "{:?}",
+        cx
+    );
+}
+
+#[test]
+fn keep_going_groups_panics_by_location() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = CheckConfig::default()
+        .keep_going(true)
+        .property(vecs(u8s()))
+        .check_fails(|v| assert!(v.len() < 2, "too long: {:?}", v));
+    assert!(
+        cx.message().starts_with("Predicate failed for argument "),
+        "{:?}",
+        cx
+    );
+}
+
+#[test]
+fn keep_going_stops_after_max_failures() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = CheckConfig::default()
+        .keep_going(true)
+        .max_failures(3)
+        .property(u8s())
+        .check_fails(|n| match n {
+            0 => Ok(()),
+            n => Err((b'a' + n % 26) as char),
+        });
+    assert!(
+        cx.message().starts_with("Found 3 distinct failures"),
+        "{:?}",
+        cx
+    );
+}
+
+#[test]
+fn keep_going_groups_failures_by_user_key() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = CheckConfig::default()
+        .keep_going(true)
+        .property(u8s())
+        .group_failures_by(|_| "everything".to_string())
+        .check_fails(odd_or_large);
+    assert!(
+        cx.message().starts_with("Predicate failed for argument "),
+        "{:?}",
+        cx
+    );
+}
@@ -0,0 +1,83 @@
+extern crate suppositions;
+
+use suppositions::generators::*;
+use suppositions::*;
+
+#[test]
+fn well_behaved_types_obey_the_laws() {
+    laws::of(vecs(u8s()))
+        .ord()
+        .eq_hash()
+        .clone_eq()
+        .monoid(Vec::new, |mut a, b| {
+            a.extend(b);
+            a
+        });
+    laws::of(u32s()).round_trip(|n| n.to_string(), |s| s.parse::<u32>());
+}
+
+// Equality ignores the second field, but hashing does not.
+#[derive(Debug, Clone)]
+struct Tagged(u32, u8);
+
+impl PartialEq for Tagged {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Tagged {}
+
+impl std::hash::Hash for Tagged {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.1.hash(state);
+    }
+}
+
+#[test]
+fn law_failures_name_the_law() {
+    let broken = laws::of((u32s(), u8s()).map(|(a, b)| Tagged(a, b))).check_fails(|l| {
+        l.eq_hash();
+    });
+    assert_eq!(broken.name(), "Hash: equal values hash equally");
+    assert!(
+        broken
+            .message()
+            .starts_with("Predicate failed for argument Ok((Tagged(0, "),
+        "{}",
+        broken.message()
+    );
+}
+
+// Values are equal if either of their fields are.
+#[derive(Debug, Clone)]
+struct EitherEq(u32, u32);
+
+impl PartialEq for EitherEq {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 || self.1 == other.1
+    }
+}
+
+impl Eq for EitherEq {}
+
+impl std::hash::Hash for EitherEq {
+    fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+}
+
+#[test]
+fn intransitive_equality_breaks_the_laws() {
+    let broken = laws::of((u32s(), u32s()).map(|(a, b)| EitherEq(a, b))).check_fails(|l| {
+        l.eq_hash();
+    });
+    assert_eq!(broken.name(), "Eq: eq is transitive");
+}
+
+#[test]
+fn round_trip_decoding_errors_are_failures() {
+    let broken = laws::of(u8s()).check_fails(|l| {
+        l.round_trip(|n| format!("{:x}", n), |s| s.parse::<u8>());
+    });
+    assert_eq!(broken.name(), "Round trip: decode inverts encode");
+}
@@ -0,0 +1,58 @@
+extern crate suppositions;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use suppositions::data::*;
+use suppositions::generators::*;
+use suppositions::*;
+
+#[derive(Debug, Default, Clone)]
+struct EventCounter(Rc<RefCell<BTreeMap<&'static str, usize>>>);
+
+impl Observer for EventCounter {
+    fn observe(&mut self, event: &Event) {
+        let name = match *event {
+            Event::CaseGenerated(_) => "generated",
+            Event::CasePassed(_) => "passed",
+            Event::CaseSkipped(_) => "skipped",
+            Event::FailureFound(_) => "failed",
+            Event::CandidateTried(_) => "tried",
+            Event::CandidateAccepted(_) => "accepted",
+            Event::ShrinkFinished(_) => "finished",
+        };
+        *self.0.borrow_mut().entry(name).or_insert(0) += 1;
+    }
+}
+
+impl EventCounter {
+    fn count(&self, name: &str) -> usize {
+        self.0.borrow().get(name).cloned().unwrap_or(0)
+    }
+}
+
+#[test]
+fn observer_sees_each_case_generated() {
+    let counter = EventCounter::default();
+    property(u8s().filter(|&n| n < 128))
+        .with_observer(counter.clone())
+        .check(|_| true);
+
+    assert_eq!(counter.count("generated"), 100);
+    assert_eq!(counter.count("passed"), 100);
+    assert!(counter.count("skipped") > 0, "{:?}", counter);
+    assert_eq!(counter.count("failed"), 0);
+}
+
+#[test]
+fn observer_sees_shrinking_progress() {
+    let counter = EventCounter::default();
+    property(u8s())
+        .with_observer(counter.clone())
+        .check_fails(|n| n < 10);
+
+    assert_eq!(counter.count("failed"), 1);
+    assert!(counter.count("tried") > 0, "{:?}", counter);
+    assert!(counter.count("accepted") > 0, "{:?}", counter);
+    assert_eq!(counter.count("finished"), 1);
+}
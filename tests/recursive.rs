@@ -4,13 +4,14 @@ extern crate log;
 extern crate suppositions;
 
 use std::ops::Add;
+use suppositions::data::*;
 use suppositions::generators::*;
 use suppositions::*;
 
 // Hutton's razor as an example.
 // Demonstrates recursive generators.
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Lit(u8),
     Add(Box<Expr>, Box<Expr>),
@@ -24,6 +25,29 @@ impl Add<Expr> for Expr {
 }
 
 impl Expr {
+    fn literals(&self) -> Vec<u8> {
+        match *self {
+            Expr::Lit(n) => vec![n],
+            Expr::Add(ref a, ref b) => {
+                let mut lits = a.literals();
+                lits.extend(b.literals());
+                lits
+            }
+        }
+    }
+
+    fn subterms(&self) -> Vec<Expr> {
+        match *self {
+            Expr::Lit(_) => vec![],
+            Expr::Add(ref a, ref b) => {
+                let mut terms = vec![(**a).clone(), (**b).clone()];
+                terms.extend(a.subterms());
+                terms.extend(b.subterms());
+                terms
+            }
+        }
+    }
+
     fn eval(&self) -> u64 {
         match self {
             &Expr::Lit(n) => n as u64,
@@ -49,3 +73,57 @@ fn add_adds() {
         assert_eq!(a.eval() + b.eval(), (a + b).eval())
     });
 }
+
+#[test]
+fn shrinks_nested_expressions_to_a_single_literal() {
+    env_logger::try_init().unwrap_or_default();
+    let failure =
+        property(expr_gen()).check_fails(|e| assert!(e.literals().iter().all(|&n| n < 100)));
+    match *failure.value() {
+        Expr::Lit(100) => (),
+        ref other => panic!("Expected Lit(100), got {:?}", other),
+    }
+}
+
+#[derive(Default)]
+struct FirstAccepted(Option<InfoPool>);
+
+impl Observer for FirstAccepted {
+    fn observe(&mut self, event: &Event) {
+        if let Event::CandidateAccepted(p) = *event {
+            self.0 = self.0.take().or_else(|| Some(p.clone()));
+        }
+    }
+}
+
+#[test]
+fn shrinking_replaces_expressions_with_failing_subexpressions() {
+    env_logger::try_init().unwrap_or_default();
+    // `one_of` picks `add` for a choice of 0xffffffff, and `lit` for zero.
+    let add = vec![0xff; 4];
+    let lit = |n| vec![0, 0, 0, 0, n];
+    let pool = InfoPool::of_vec([add.clone(), lit(0), add, lit(100), lit(0)].concat());
+    let gen = expr_gen();
+    let orig = gen.generate_from(&pool).expect("generate");
+    assert_eq!(orig.literals(), vec![0, 100, 0]);
+
+    let mut first = FirstAccepted::default();
+    let fails = |t: &mut InfoRecorder<InfoReplay>| {
+        t.draw(&gen)
+            .map(|e| e.literals().iter().any(|&n| n >= 100))
+            .unwrap_or(false)
+    };
+    minimize_observed(&pool, &fails, &mut first);
+
+    // Removing or lowering bytes can't turn the whole expression into one
+    // of its parts in a single step; hoisting can.
+    let shrunk = gen
+        .generate_from(&first.0.expect("accepted"))
+        .expect("generate");
+    assert!(
+        orig.subterms().contains(&shrunk),
+        "{:?} is not a subterm of {:?}",
+        shrunk,
+        orig
+    );
+}
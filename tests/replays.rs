@@ -0,0 +1,26 @@
+extern crate suppositions;
+
+use suppositions::generators::*;
+use suppositions::*;
+
+#[test]
+fn consistent_failures_reproduce_on_every_replay() {
+    let cx = property(booleans()).check_fails(|_| false);
+    assert!(cx.message().ends_with("failed on 3/3 replays"), "{:?}", cx);
+}
+
+#[test]
+fn intermittent_failures_are_reported_as_flaky() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    let calls = AtomicUsize::new(0);
+    let cx = property(u8s()).check_fails(|n| {
+        let nth = calls.fetch_add(1, Ordering::SeqCst);
+        n < 128 || nth % 2 == 1
+    });
+    assert!(
+        cx.message()
+            .starts_with("Flaky failure: Predicate failed for argument "),
+        "{:?}",
+        cx
+    );
+}
@@ -0,0 +1,59 @@
+extern crate env_logger;
+extern crate suppositions;
+
+use suppositions::generators::*;
+use suppositions::*;
+
+// Values large enough to fail are common, but zero is vanishingly unlikely
+// to be generated; so we'll only divide by zero if we slip whilst shrinking.
+fn large_or_divide_by_zero(n: u32) {
+    let _ = 100 / n;
+    assert!(n < 4_000_000_000, "too big: {}", n);
+}
+
+// As above, but returning a failing result rather than panicking.
+fn large_or_zero(n: u32) -> Result<(), String> {
+    if n == 0 {
+        Err("zero".to_string())
+    } else if n >= 4_000_000_000 {
+        Err(format!("too big: {}", n))
+    } else {
+        Ok(())
+    }
+}
+
+#[test]
+fn shrinking_does_not_slip_to_different_failure() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = property(u32s()).check_fails(large_or_divide_by_zero);
+    assert!(cx.message().contains("too big"), "{:?}", cx);
+    let cx = property(u32s())
+        .group_failures_by(|f| f.fingerprint())
+        .check_fails(large_or_zero);
+    assert_eq!(cx.into_value(), 4_000_000_000);
+}
+
+#[test]
+fn messages_that_show_the_input_do_not_stop_shrinking() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = property(vecs(u8s())).check_fails(|v| assert!(v.len() <= 2, "bad {:?}", v));
+    assert_eq!(cx.into_value(), vec![0, 0, 0]);
+    let cx = property(vecs(u8s())).check_fails(|v| {
+        if v.len() > 2 {
+            Err(format!("{:?} too long", v))
+        } else {
+            Ok(())
+        }
+    });
+    assert_eq!(cx.into_value(), vec![0, 0, 0]);
+}
+
+#[test]
+fn shrinking_can_be_allowed_to_slip() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = CheckConfig::default()
+        .allow_slippage(true)
+        .property(u32s())
+        .check_fails(large_or_divide_by_zero);
+    assert_eq!(cx.into_value(), 0);
+}
@@ -3,6 +3,8 @@ extern crate env_logger;
 extern crate log;
 extern crate suppositions;
 
+use std::collections::HashSet;
+use suppositions::data::*;
 use suppositions::generators::*;
 use suppositions::*;
 
@@ -17,20 +19,19 @@ fn some_approximation_of_usage() {
 
 // In this case, we reverse the last three items.
 #[test]
-#[should_panic(expected = "Predicate failed for argument ")]
 fn some_approximation_of_failing_example() {
     env_logger::try_init().unwrap_or_default();
-    property(vecs(booleans())).check(|l| {
+    let cx = property(vecs(booleans())).check_fails(|l| {
         let rev = l.iter().cloned().rev().take(3).collect::<Vec<_>>();
         let rev2 = rev.into_iter().rev().collect::<Vec<_>>();
         info!("in:{:?}; out:{:?}; ok? {:?}", l, rev2, &rev2 == &l);
         return rev2 == l;
-    })
+    });
+    assert_eq!(cx.value().len(), 4, "{:?}", cx);
 }
 
 // http://matt.might.net/articles/quick-quickcheck/
 #[test]
-#[should_panic(expected = "Predicate failed for argument ")]
 fn mersenne_conjecture() {
     env_logger::try_init().unwrap_or_default();
     fn is_prime(n: u64) -> bool {
@@ -48,14 +49,18 @@ fn mersenne_conjecture() {
         debug!("mersenne_conjecture n: {}; prime? {}", n, primep);
         n < 64 && primep
     }))
-    .check(|n| is_prime((1u64 << n) - 1))
+    .check_fails(|n| is_prime((1u64 << n) - 1));
 }
 
 #[test]
-#[should_panic(expected = "Predicate failed for argument ")]
 fn trivial_failure() {
     env_logger::try_init().unwrap_or_default();
-    property(booleans()).check(|_| false)
+    let cx = property(booleans()).check_fails(|_| false);
+    assert!(
+        cx.message().starts_with("Predicate failed for argument "),
+        "{:?}",
+        cx
+    );
 }
 
 #[test]
@@ -64,24 +69,25 @@ fn trivial_pass() {
 }
 
 #[test]
-#[should_panic(expected = "Predicate failed for argument ")]
 fn value_dependent() {
-    property(vecs(booleans())).check(|v| {
+    let cx = property(vecs(booleans())).check_fails(|v| {
         println!("Check: {:?}", v);
         !v.into_iter().any(|t| t)
-    })
+    });
+    assert_eq!(cx.into_value(), vec![true]);
 }
 
 #[test]
-#[should_panic(expected = "Predicate failed for argument ")]
 fn trivial_result_failure() {
-    property(booleans()).check(|_| -> Result<(), ()> { Err(()) })
+    let cx = property(booleans()).check_fails(|_| -> Result<(), ()> { Err(()) });
+    assert!(!cx.into_value());
 }
 
 #[test]
-#[should_panic(expected = "horrible failure")]
 fn trivial_result_includes_failing_result() {
-    property(booleans()).check(|_| -> Result<(), &'static str> { Err("horrible failure") })
+    let cx = property(booleans())
+        .check_fails(|_| -> Result<(), &'static str> { Err("horrible failure") });
+    assert!(cx.message().contains("horrible failure"), "{:?}", cx);
 }
 
 #[test]
@@ -90,54 +96,73 @@ fn trivial_result_pass() {
 }
 
 #[test]
-#[should_panic(expected = "Predicate failed for argument ")]
 fn trivial_panic_failure() {
-    property(booleans()).check(|_| -> () { panic!("Big bad boom") })
+    let cx = property(booleans()).check_fails(|_| -> () { panic!("Big bad boom") });
+    assert!(!cx.into_value());
 }
 
 #[test]
-#[should_panic(expected = "Big bad boom")]
 fn panic_includes_failure_message() {
-    property(booleans()).check(|_| -> () { panic!("Big bad boom") })
+    let cx = property(booleans()).check_fails(|_| -> () { panic!("Big bad boom") });
+    assert!(cx.message().contains("Big bad boom"), "{:?}", cx);
 }
 
-/*
-Currently fails with:
-```
-    ... 'Predicate failed for argument Ok(72057594037927936); check returned Ok(false)'...
-    note: Panic did not include expected string '123457890'
-```
+#[test]
+#[should_panic(expected = "Expected a failure, but none found in 100 tests")]
+fn check_fails_requires_a_failure() {
+    property(booleans()).check_fails(|_| true);
+}
 
-This occurs because:
- * We shrink by removal before we shrink by value
- * We backfill empty chunks from the iterator with zeroes
- * So, this fails on 72057594037927936, or 0x100000000000000 with a single-byte pool.
-*/
-#[ignore]
+// Removing bytes from the pool shifts the remaining ones into more
+// significant positions, eg: 0x100000000000000 from a single byte pool. So we
+// need to shrink the integer as a whole.
 #[test]
-#[should_panic(expected = "12345")]
-fn panic_includes_minimal_example_padding_error() {
+fn minimal_example_padding_error() {
     env_logger::try_init().unwrap_or_default();
-    property(u64s()).check(|n| n < 12345);
+    let cx = property(u64s()).check_fails(|n| n < 12345);
+    assert_eq!(cx.into_value(), 12345);
 }
 
-/*
-Currently fails with:
-```
----- panic_includes_minimal_example_rounding_errors stdout ----
-    ... 'Predicate failed for argument Ok(1245187); check returned Ok(false)'...
-    note: Panic did not include expected string '1234567'
-```
+// Shrinking each pool byte in turn won't find this, as 0x101 may fail, but
+// 0x001 won't, even if 0x0f1 would.
+#[test]
+fn minimal_example_rounding_errors() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = property(u64s()).check_fails(|n| !((n & 1 == 1) && n >= 1234567));
+    assert_eq!(cx.into_value(), 1234567);
+}
 
-This occurrs because:
- * We shrink each individual pool byte in turn,
- * So 0x101 may fail, but 0x001 won't, even if 0x0f1 would.
-*/
+#[test]
+fn list_elements_shrink_to_sorted_order() {
+    env_logger::try_init().unwrap_or_default();
+    let gen = vecs(u16s());
+    // Other shrinks can only reorder the elements by shuffling bytes between
+    // them, so we start from a pool where every element, and every flag that
+    // says another element follows, is already minimal, but the elements are
+    // out of order.
+    let more = vec![0x17, 0x45, 0xd2, 0x11];
+    let pool = InfoPool::of_vec([&more[..], &[0, 11], &more, &[0, 10], &[0; 4]].concat());
+    assert_eq!(gen.generate_from(&pool).expect("generate"), vec![11, 10]);
+    let fails = |t: &mut InfoRecorder<InfoReplay>| {
+        t.draw(&gen)
+            .map(|v| v.iter().filter(|&&n| n >= 10).collect::<HashSet<_>>().len() >= 2)
+            .unwrap_or(false)
+    };
+    let shrunk = gen.generate_from(&minimize(&pool, &fails));
+    assert_eq!(shrunk.expect("generate"), vec![10, 11]);
+}
 
-#[ignore]
 #[test]
-#[should_panic(expected = "1234567")]
-fn panic_includes_minimal_example_rounding_errors() {
+fn equivalent_failures_shrink_to_identical_examples() {
     env_logger::try_init().unwrap_or_default();
-    property(u64s()).check(|n| !((n & 1 == 1) && n >= 1234567));
+    let examples = (0..5)
+        .map(|seed| {
+            CheckConfig::default()
+                .seed(seed)
+                .property(vecs(u8s()))
+                .check_fails(|v| v.iter().all(|&n| n % 10 != 7))
+                .into_value()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(examples, vec![vec![7]; 5]);
 }
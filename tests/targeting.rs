@@ -0,0 +1,23 @@
+extern crate env_logger;
+extern crate suppositions;
+
+use suppositions::generators::*;
+use suppositions::*;
+
+#[test]
+fn targeting_finds_extreme_values() {
+    env_logger::try_init().unwrap_or_default();
+    let cx = CheckConfig::default()
+        .num_tests(1000)
+        .property(u32s())
+        .check_fails(|n| {
+            target("n", n as f64);
+            n <= 0xfff0_0000
+        });
+    assert!(*cx.value() > 0xfff0_0000, "{:?}", cx);
+    assert!(
+        cx.message().contains("\nBest target scores:\n  n: "),
+        "{}",
+        cx.message()
+    );
+}